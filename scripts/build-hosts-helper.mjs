@@ -0,0 +1,33 @@
+// Builds the focus-hosts-helper binary and puts it where `bundle.externalBin` expects it
+// (src-tauri/binaries/focus-hosts-helper-<target triple>), so installers ship it next to the
+// app. Runs as part of `beforeBuildCommand`.
+import { execFileSync } from "node:child_process";
+import { copyFileSync, mkdirSync, statSync } from "node:fs";
+import { dirname, join } from "node:path";
+import { fileURLToPath } from "node:url";
+
+const tauriDir = join(dirname(fileURLToPath(import.meta.url)), "..", "src-tauri");
+
+function hostTriple() {
+  const info = execFileSync("rustc", ["-vV"], { encoding: "utf8" });
+  const host = info.match(/^host: (\S+)$/m);
+  if (!host) throw new Error("couldn't read the host target triple from `rustc -vV`");
+  return host[1];
+}
+
+const triple = process.env.TAURI_ENV_TARGET_TRIPLE || hostTriple();
+const ext = triple.includes("windows") ? ".exe" : "";
+
+execFileSync(
+  "cargo",
+  ["build", "--release", "--bin", "focus-hosts-helper", "--target", triple],
+  { cwd: tauriDir, stdio: "inherit" },
+);
+
+const built = join(tauriDir, "target", triple, "release", `focus-hosts-helper${ext}`);
+if (statSync(built).size === 0) throw new Error(`${built} is empty`);
+
+const sidecar = join(tauriDir, "binaries", `focus-hosts-helper-${triple}${ext}`);
+mkdirSync(dirname(sidecar), { recursive: true });
+copyFileSync(built, sidecar);
+console.log(`focus-hosts-helper -> ${sidecar}`);
@@ -1,3 +1,25 @@
+use std::fs;
+use std::path::Path;
+
 fn main() {
+    ensure_hosts_helper_sidecar();
     tauri_build::build()
 }
+
+/// tauri-build refuses to run unless every `bundle.externalBin` file exists, but the hosts
+/// helper is a bin target of this same package and can only be built after this script.
+/// Until `npm run build:hosts-helper` copies the real binary in, leave an empty stand-in so
+/// `cargo build` / `tauri dev` work from a fresh checkout.
+fn ensure_hosts_helper_sidecar() {
+    let target = std::env::var("TARGET").expect("cargo sets TARGET for build scripts");
+    let ext = if target.contains("windows") {
+        ".exe"
+    } else {
+        ""
+    };
+    let sidecar = Path::new("binaries").join(format!("focus-hosts-helper-{target}{ext}"));
+    if !sidecar.exists() {
+        fs::create_dir_all("binaries").expect("failed to create src-tauri/binaries");
+        fs::write(&sidecar, b"").expect("failed to create the hosts helper placeholder");
+    }
+}
@@ -0,0 +1,7 @@
+// Small elevated process that owns hosts file writes, so the main app can run unprivileged.
+fn main() {
+    if let Err(e) = test_lib::run_hosts_helper() {
+        eprintln!("focus-hosts-helper: {e}");
+        std::process::exit(1);
+    }
+}
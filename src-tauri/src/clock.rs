@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Local, NaiveDateTime, Utc};
+
+/// Source of the current time for the timer worker and the database, so tests can control
+/// it instead of sleeping.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Wall-clock time in the user's time zone, which blocking schedules are written in.
+    fn local_now(&self) -> NaiveDateTime {
+        self.now().with_timezone(&Local).naive_local()
+    }
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The real wall clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+pub fn system_clock() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// A clock that only moves when told to.
+#[cfg(test)]
+pub struct FakeClock {
+    now: std::sync::Mutex<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+impl FakeClock {
+    pub fn new(now: DateTime<Utc>) -> Arc<Self> {
+        Arc::new(Self {
+            now: std::sync::Mutex::new(now),
+        })
+    }
+
+    /// Starts at `rfc3339`, e.g. "2024-01-01T09:00:00Z".
+    pub fn at(rfc3339: &str) -> Arc<Self> {
+        Self::new(
+            DateTime::parse_from_rfc3339(rfc3339)
+                .expect("valid timestamp")
+                .with_timezone(&Utc),
+        )
+    }
+
+    pub fn advance(&self, by: chrono::Duration) {
+        *self.now.lock().unwrap() += by;
+    }
+
+    pub fn advance_secs(&self, seconds: i64) {
+        self.advance(chrono::Duration::seconds(seconds));
+    }
+}
+
+#[cfg(test)]
+impl Clock for FakeClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap()
+    }
+
+    /// Treats UTC as local time so tests don't depend on the machine's time zone.
+    fn local_now(&self) -> NaiveDateTime {
+        self.now().naive_utc()
+    }
+}
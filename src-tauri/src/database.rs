@@ -1,72 +1,66 @@
 use std::sync::{Arc, Mutex};
 
-use chrono::{Duration as ChronoDuration, Utc};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
 use rusqlite::{params, Connection, OptionalExtension};
 use tauri::AppHandle;
 use tauri::Manager;
 
-use crate::models::{Settings, TodayStatistics};
+use crate::clock::SharedClock;
+use crate::error::AppError;
+use crate::focus_blocker::ActiveBlocklist;
+use crate::migrations;
+use crate::models::{
+    AppBlockAction, BlockMode, BlockSchedule, BlockedApp, BlockedSite, BlockingMethod,
+    BlocklistProfile, Settings, TodayStatistics,
+};
+use crate::session_lock::SessionLock;
 use crate::timer::Phase;
 
 #[derive(Clone)]
 pub struct Database {
     conn: Arc<Mutex<Connection>>,
+    /// Decides what "today" is for the statistics.
+    clock: SharedClock,
 }
 
 impl Database {
-    pub fn new(app: &AppHandle) -> Result<Self, String> {
-        let mut dir = app
-            .path()
-            .app_data_dir()
-            .map_err(|e| format!("failed to resolve app data dir: {e}"))?;
+    pub fn new(app: &AppHandle, clock: SharedClock) -> Result<Self, AppError> {
+        let mut dir = app.path().app_data_dir().map_err(|e| AppError::Io {
+            message: format!("failed to resolve app data dir: {e}"),
+        })?;
         std::fs::create_dir_all(&dir)
-            .map_err(|e| format!("failed to create app data dir {dir:?}: {e}"))?;
+            .map_err(|e| AppError::io(format!("failed to create app data dir {dir:?}"), e))?;
         dir.push("focus_timer.sqlite3");
 
         let conn = Connection::open(&dir)
-            .map_err(|e| format!("failed to open database {dir:?}: {e}"))?;
+            .map_err(|e| AppError::database(&format!("failed to open database {dir:?}"), e))?;
+        Self::from_connection(conn, clock)
+    }
+
+    /// A fresh, fully migrated database that lives only as long as the value.
+    #[cfg(test)]
+    pub fn in_memory(clock: SharedClock) -> Result<Self, AppError> {
+        let conn = Connection::open_in_memory()
+            .map_err(|e| AppError::database("failed to open in-memory database", e))?;
+        Self::from_connection(conn, clock)
+    }
 
+    fn from_connection(conn: Connection, clock: SharedClock) -> Result<Self, AppError> {
         let db = Self {
             conn: Arc::new(Mutex::new(conn)),
+            clock,
         };
         db.migrate()?;
         db.ensure_default_settings()?;
         Ok(db)
     }
 
-    fn migrate(&self) -> Result<(), String> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|_| "db mutex poisoned".to_string())?;
-
-        conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS settings (
-                id INTEGER PRIMARY KEY CHECK (id = 1),
-                work_minutes INTEGER NOT NULL,
-                short_break_minutes INTEGER NOT NULL,
-                long_break_minutes INTEGER NOT NULL,
-                long_break_after INTEGER NOT NULL,
-                daily_goal INTEGER NOT NULL
-            );
-
-            CREATE TABLE IF NOT EXISTS sessions (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                date TEXT NOT NULL,
-                start_time TEXT NOT NULL,
-                end_time TEXT NOT NULL,
-                duration_minutes INTEGER NOT NULL,
-                phase_type TEXT NOT NULL,
-                completed INTEGER NOT NULL DEFAULT 1,
-                notes TEXT
-            );",
-        )
-        .map_err(|e| format!("failed to migrate database: {e}"))?;
-
-        Ok(())
+    fn migrate(&self) -> Result<(), AppError> {
+        let mut conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
+        migrations::run(&mut conn)
     }
 
-    fn ensure_default_settings(&self) -> Result<(), String> {
+    fn ensure_default_settings(&self) -> Result<(), AppError> {
         let existing = self.load_settings().ok();
         if existing.is_some() {
             return Ok(());
@@ -75,109 +69,168 @@ impl Database {
         self.save_settings(&Settings::default())
     }
 
-    pub fn load_settings(&self) -> Result<Settings, String> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|_| "db mutex poisoned".to_string())?;
+    pub fn load_settings(&self) -> Result<Settings, AppError> {
+        let conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
 
         let row = conn
             .query_row(
-                "SELECT work_minutes, short_break_minutes, long_break_minutes, long_break_after, daily_goal
+                "SELECT work_minutes, short_break_minutes, long_break_minutes, long_break_after, daily_goal,
+                        active_profile_id, block_subdomains, hosts_path, use_hosts_helper,
+                        blocking_method, dns_listen_addr, dns_upstream, proxy_listen_addr, block_mode,
+                        app_block_action
                  FROM settings WHERE id = 1",
                 [],
                 |r| {
+                    let defaults = Settings::default();
                     Ok(Settings {
                         work_minutes: r.get::<_, i64>(0)? as u32,
                         short_break_minutes: r.get::<_, i64>(1)? as u32,
                         long_break_minutes: r.get::<_, i64>(2)? as u32,
                         long_break_after: r.get::<_, i64>(3)? as u32,
                         daily_goal: r.get::<_, i64>(4)? as u32,
+                        active_profile_id: r.get(5)?,
+                        block_subdomains: r
+                            .get::<_, Option<String>>(6)?
+                            .map(|list| split_list(&list))
+                            .unwrap_or(defaults.block_subdomains),
+                        hosts_path: r.get(7)?,
+                        use_hosts_helper: r.get::<_, i64>(8)? != 0,
+                        blocking_method: BlockingMethod::parse(&r.get::<_, String>(9)?),
+                        dns_listen_addr: r
+                            .get::<_, Option<String>>(10)?
+                            .unwrap_or(defaults.dns_listen_addr),
+                        dns_upstream: r
+                            .get::<_, Option<String>>(11)?
+                            .unwrap_or(defaults.dns_upstream),
+                        proxy_listen_addr: r
+                            .get::<_, Option<String>>(12)?
+                            .unwrap_or(defaults.proxy_listen_addr),
+                        block_mode: BlockMode::parse(&r.get::<_, String>(13)?),
+                        app_block_action: AppBlockAction::parse(&r.get::<_, String>(14)?),
                     })
                 },
             )
             .optional()
-            .map_err(|e| format!("failed to read settings: {e}"))?;
+            .map_err(|e| AppError::database("failed to read settings", e))?;
 
-        row.ok_or_else(|| "settings not initialized".to_string())
+        row.ok_or_else(|| AppError::Database {
+            message: "settings not initialized".to_string(),
+        })
     }
 
-    pub fn save_settings(&self, settings: &Settings) -> Result<(), String> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|_| "db mutex poisoned".to_string())?;
+    pub fn save_settings(&self, settings: &Settings) -> Result<(), AppError> {
+        let conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
 
         conn.execute(
-            "INSERT INTO settings (id, work_minutes, short_break_minutes, long_break_minutes, long_break_after, daily_goal)
-             VALUES (1, ?1, ?2, ?3, ?4, ?5)
+            "INSERT INTO settings (id, work_minutes, short_break_minutes, long_break_minutes, long_break_after, daily_goal,
+                                   active_profile_id, block_subdomains, hosts_path, use_hosts_helper,
+                                   blocking_method, dns_listen_addr, dns_upstream, proxy_listen_addr,
+                                   block_mode, app_block_action)
+             VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
              ON CONFLICT(id) DO UPDATE SET
                work_minutes = excluded.work_minutes,
                short_break_minutes = excluded.short_break_minutes,
                long_break_minutes = excluded.long_break_minutes,
                long_break_after = excluded.long_break_after,
-               daily_goal = excluded.daily_goal",
+               daily_goal = excluded.daily_goal,
+               active_profile_id = excluded.active_profile_id,
+               block_subdomains = excluded.block_subdomains,
+               hosts_path = excluded.hosts_path,
+               use_hosts_helper = excluded.use_hosts_helper,
+               blocking_method = excluded.blocking_method,
+               dns_listen_addr = excluded.dns_listen_addr,
+               dns_upstream = excluded.dns_upstream,
+               proxy_listen_addr = excluded.proxy_listen_addr,
+               block_mode = excluded.block_mode,
+               app_block_action = excluded.app_block_action",
             params![
                 settings.work_minutes as i64,
                 settings.short_break_minutes as i64,
                 settings.long_break_minutes as i64,
                 settings.long_break_after as i64,
-                settings.daily_goal as i64
+                settings.daily_goal as i64,
+                settings.active_profile_id,
+                settings.block_subdomains.join(","),
+                settings.hosts_path,
+                settings.use_hosts_helper as i64,
+                settings.blocking_method.as_str(),
+                settings.dns_listen_addr,
+                settings.dns_upstream,
+                settings.proxy_listen_addr,
+                settings.block_mode.as_str(),
+                settings.app_block_action.as_str()
             ],
         )
-        .map_err(|e| format!("failed to save settings: {e}"))?;
+        .map_err(|e| AppError::database("failed to save settings", e))?;
 
         Ok(())
     }
 
-    pub fn insert_completed_phase(&self, phase: Phase, duration_seconds: u32) -> Result<(), String> {
-        let now = Utc::now();
-        let start = now - ChronoDuration::seconds(duration_seconds as i64);
-        let date = now.date_naive().to_string();
-        let phase_type = match phase {
-            Phase::Work => "work",
-            Phase::ShortBreak | Phase::LongBreak => "break",
-        };
+    pub fn insert_completed_phase(
+        &self,
+        phase: Phase,
+        duration_seconds: u32,
+        ended_at: DateTime<Utc>,
+    ) -> Result<(), AppError> {
+        self.insert_phase(phase, duration_seconds, ended_at, true)
+    }
+
+    /// Records a phase that was cut short; `elapsed_seconds` is how long it actually ran.
+    pub fn insert_incomplete_phase(
+        &self,
+        phase: Phase,
+        elapsed_seconds: u32,
+        ended_at: DateTime<Utc>,
+    ) -> Result<(), AppError> {
+        self.insert_phase(phase, elapsed_seconds, ended_at, false)
+    }
 
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|_| "db mutex poisoned".to_string())?;
+    fn insert_phase(
+        &self,
+        phase: Phase,
+        duration_seconds: u32,
+        ended_at: DateTime<Utc>,
+        completed: bool,
+    ) -> Result<(), AppError> {
+        let start = ended_at - ChronoDuration::seconds(duration_seconds as i64);
+        let date = ended_at.date_naive().to_string();
+
+        let conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
 
         conn.execute(
-            "INSERT INTO sessions (date, start_time, end_time, duration_minutes, phase_type, completed, notes)
-             VALUES (?1, ?2, ?3, ?4, ?5, 1, NULL)",
+            "INSERT INTO sessions (date, start_time, end_time, duration_seconds, phase_type, completed, notes)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, NULL)",
             params![
                 date,
                 start.to_rfc3339(),
-                now.to_rfc3339(),
-                (duration_seconds / 60) as i64,
-                phase_type
+                ended_at.to_rfc3339(),
+                duration_seconds as i64,
+                phase.as_str(),
+                completed as i64
             ],
         )
-        .map_err(|e| format!("failed to insert session: {e}"))?;
+        .map_err(|e| AppError::database("failed to insert session", e))?;
 
         Ok(())
     }
 
-    pub fn get_today_statistics(&self) -> Result<TodayStatistics, String> {
+    pub fn get_today_statistics(&self) -> Result<TodayStatistics, AppError> {
         let settings = self.load_settings()?;
-        let date = Utc::now().date_naive().to_string();
+        let date = self.clock.now().date_naive().to_string();
 
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|_| "db mutex poisoned".to_string())?;
+        let conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
 
+        // Interrupted work still counts as time spent focusing. Summed in seconds so short
+        // phases add up instead of each being truncated.
         let total_focus_minutes: u32 = conn
             .query_row(
-                "SELECT COALESCE(SUM(duration_minutes), 0)
+                "SELECT COALESCE(SUM(duration_seconds), 0) / 60
                  FROM sessions
-                 WHERE date = ?1 AND phase_type = 'work' AND completed = 1",
+                 WHERE date = ?1 AND phase_type = 'work'",
                 params![date.clone()],
                 |r| Ok(r.get::<_, i64>(0)? as u32),
             )
-            .map_err(|e| format!("failed to compute total focus minutes: {e}"))?;
+            .map_err(|e| AppError::database("failed to compute total focus minutes", e))?;
 
         let work_sessions_completed: u32 = conn
             .query_row(
@@ -187,17 +240,33 @@ impl Database {
                 params![date.clone()],
                 |r| Ok(r.get::<_, i64>(0)? as u32),
             )
-            .map_err(|e| format!("failed to compute sessions completed: {e}"))?;
+            .map_err(|e| AppError::database("failed to compute sessions completed", e))?;
 
-        let breaks_taken: u32 = conn
+        let (short_breaks_taken, long_breaks_taken): (u32, u32) = conn
             .query_row(
-                "SELECT COALESCE(COUNT(*), 0)
+                "SELECT
+                    COALESCE(SUM(phase_type = 'short_break'), 0),
+                    COALESCE(SUM(phase_type = 'long_break'), 0)
                  FROM sessions
-                 WHERE date = ?1 AND phase_type = 'break' AND completed = 1",
+                 WHERE date = ?1 AND completed = 1",
+                params![date.clone()],
+                |r| Ok((r.get::<_, i64>(0)? as u32, r.get::<_, i64>(1)? as u32)),
+            )
+            .map_err(|e| AppError::database("failed to compute breaks taken", e))?;
+
+        let interruptions: u32 = conn
+            .query_row(
+                "SELECT COUNT(*)
+                 FROM sessions
+                 WHERE date = ?1 AND phase_type = 'work' AND completed = 0",
                 params![date.clone()],
                 |r| Ok(r.get::<_, i64>(0)? as u32),
             )
-            .map_err(|e| format!("failed to compute breaks taken: {e}"))?;
+            .map_err(|e| AppError::database("failed to compute interruptions", e))?;
+
+        let work_sessions_started = work_sessions_completed + interruptions;
+        let completion_rate = (work_sessions_started > 0)
+            .then(|| f64::from(work_sessions_completed) / f64::from(work_sessions_started));
 
         let longest_streak: u32 = conn
             .query_row(
@@ -210,16 +279,593 @@ impl Database {
                 [],
                 |r| Ok(r.get::<_, i64>(0)? as u32),
             )
-            .map_err(|e| format!("failed to compute longest streak: {e}"))?;
+            .map_err(|e| AppError::database("failed to compute longest streak", e))?;
 
         Ok(TodayStatistics {
             date,
             total_focus_minutes,
             work_sessions_completed,
-            breaks_taken,
+            breaks_taken: short_breaks_taken + long_breaks_taken,
+            short_breaks_taken,
+            long_breaks_taken,
+            interruptions,
+            completion_rate,
             current_streak: work_sessions_completed,
             longest_streak,
             daily_goal: settings.daily_goal,
         })
     }
+
+    pub fn list_blocklist_profiles(&self) -> Result<Vec<BlocklistProfile>, AppError> {
+        let conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT p.id, p.name, p.suppress_notifications,
+                        (SELECT COUNT(*) FROM blocked_sites s WHERE s.profile_id = p.id)
+                 FROM blocklist_profiles p
+                 ORDER BY p.id",
+            )
+            .map_err(|e| AppError::database("failed to read blocklist profiles", e))?;
+        let rows = stmt
+            .query_map([], read_profile)
+            .map_err(|e| AppError::database("failed to read blocklist profiles", e))?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::database("failed to read blocklist profiles", e))
+    }
+
+    pub fn get_blocklist_profile(&self, id: i64) -> Result<BlocklistProfile, AppError> {
+        let conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
+
+        conn.query_row(
+            "SELECT p.id, p.name, p.suppress_notifications,
+                    (SELECT COUNT(*) FROM blocked_sites s WHERE s.profile_id = p.id)
+             FROM blocklist_profiles p
+             WHERE p.id = ?1",
+            params![id],
+            read_profile,
+        )
+        .optional()
+        .map_err(|e| AppError::database("failed to read blocklist profile", e))?
+        .ok_or_else(|| AppError::not_found(format!("blocklist profile {id} not found")))
+    }
+
+    pub fn create_blocklist_profile(
+        &self,
+        name: &str,
+        suppress_notifications: bool,
+    ) -> Result<BlocklistProfile, AppError> {
+        let id = {
+            let conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
+
+            conn.execute(
+                "INSERT INTO blocklist_profiles (name, suppress_notifications) VALUES (?1, ?2)",
+                params![name, suppress_notifications as i64],
+            )
+            .map_err(|e| AppError::database("failed to create blocklist profile", e))?;
+            conn.last_insert_rowid()
+        };
+
+        self.get_blocklist_profile(id)
+    }
+
+    pub fn update_blocklist_profile(
+        &self,
+        id: i64,
+        name: &str,
+        suppress_notifications: bool,
+    ) -> Result<BlocklistProfile, AppError> {
+        {
+            let conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
+
+            let updated = conn
+                .execute(
+                    "UPDATE blocklist_profiles SET name = ?2, suppress_notifications = ?3 WHERE id = ?1",
+                    params![id, name, suppress_notifications as i64],
+                )
+                .map_err(|e| AppError::database("failed to update blocklist profile", e))?;
+            if updated == 0 {
+                return Err(AppError::not_found(format!(
+                    "blocklist profile {id} not found"
+                )));
+            }
+        }
+
+        self.get_blocklist_profile(id)
+    }
+
+    pub fn delete_blocklist_profile(&self, id: i64) -> Result<(), AppError> {
+        let mut conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
+
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM blocklist_profiles", [], |r| r.get(0))
+            .map_err(|e| AppError::database("failed to read blocklist profiles", e))?;
+        if count <= 1 {
+            return Err(AppError::Conflict {
+                message: "the last blocklist profile can't be deleted".to_string(),
+            });
+        }
+
+        let tx = conn
+            .transaction()
+            .map_err(|e| AppError::database("failed to delete blocklist profile", e))?;
+        tx.execute(
+            "DELETE FROM blocked_sites WHERE profile_id = ?1",
+            params![id],
+        )
+        .map_err(|e| AppError::database("failed to delete blocklist profile", e))?;
+        let removed = tx
+            .execute("DELETE FROM blocklist_profiles WHERE id = ?1", params![id])
+            .map_err(|e| AppError::database("failed to delete blocklist profile", e))?;
+        if removed == 0 {
+            return Err(AppError::not_found(format!(
+                "blocklist profile {id} not found"
+            )));
+        }
+        tx.execute(
+            "UPDATE settings SET active_profile_id = NULL WHERE active_profile_id = ?1",
+            params![id],
+        )
+        .map_err(|e| AppError::database("failed to delete blocklist profile", e))?;
+        tx.commit()
+            .map_err(|e| AppError::database("failed to delete blocklist profile", e))
+    }
+
+    /// Resolves the profile selected in settings, falling back to the first profile.
+    pub fn active_blocklist_profile(&self) -> Result<BlocklistProfile, AppError> {
+        if let Some(id) = self.load_settings()?.active_profile_id {
+            if let Ok(profile) = self.get_blocklist_profile(id) {
+                return Ok(profile);
+            }
+        }
+
+        self.list_blocklist_profiles()?
+            .into_iter()
+            .next()
+            .ok_or_else(|| AppError::not_found("no blocklist profiles configured"))
+    }
+
+    pub fn load_active_blocklist(&self) -> Result<ActiveBlocklist, AppError> {
+        let settings = self.load_settings()?;
+        let profile = self.active_blocklist_profile()?;
+        let domains = self
+            .list_blocked_sites(profile.id)?
+            .into_iter()
+            .filter(|site| site.enabled)
+            .map(|site| site.domain)
+            .collect();
+
+        Ok(ActiveBlocklist {
+            domains,
+            subdomains: settings.block_subdomains,
+            suppress_notifications: profile.suppress_notifications,
+            allowlist: settings.block_mode == BlockMode::Allowlist,
+        })
+    }
+
+    pub fn list_blocked_sites(&self, profile_id: i64) -> Result<Vec<BlockedSite>, AppError> {
+        let conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, profile_id, domain, enabled FROM blocked_sites
+                 WHERE profile_id = ?1
+                 ORDER BY domain",
+            )
+            .map_err(|e| AppError::database("failed to read blocked sites", e))?;
+        let rows = stmt
+            .query_map(params![profile_id], read_blocked_site)
+            .map_err(|e| AppError::database("failed to read blocked sites", e))?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::database("failed to read blocked sites", e))
+    }
+
+    pub fn add_blocked_site(&self, profile_id: i64, domain: &str) -> Result<BlockedSite, AppError> {
+        let conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
+
+        conn.execute(
+            "INSERT INTO blocked_sites (profile_id, domain, enabled) VALUES (?1, ?2, 1)
+             ON CONFLICT(profile_id, domain) DO UPDATE SET enabled = 1",
+            params![profile_id, domain],
+        )
+        .map_err(|e| AppError::database("failed to add blocked site", e))?;
+
+        conn.query_row(
+            "SELECT id, profile_id, domain, enabled FROM blocked_sites
+             WHERE profile_id = ?1 AND domain = ?2",
+            params![profile_id, domain],
+            read_blocked_site,
+        )
+        .map_err(|e| AppError::database("failed to read blocked site", e))
+    }
+
+    pub fn remove_blocked_site(&self, id: i64) -> Result<(), AppError> {
+        let conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
+
+        let removed = conn
+            .execute("DELETE FROM blocked_sites WHERE id = ?1", params![id])
+            .map_err(|e| AppError::database("failed to remove blocked site", e))?;
+        if removed == 0 {
+            return Err(AppError::not_found(format!("blocked site {id} not found")));
+        }
+        Ok(())
+    }
+
+    pub fn set_blocked_site_enabled(&self, id: i64, enabled: bool) -> Result<(), AppError> {
+        let conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
+
+        let updated = conn
+            .execute(
+                "UPDATE blocked_sites SET enabled = ?2 WHERE id = ?1",
+                params![id, enabled as i64],
+            )
+            .map_err(|e| AppError::database("failed to update blocked site", e))?;
+        if updated == 0 {
+            return Err(AppError::not_found(format!("blocked site {id} not found")));
+        }
+        Ok(())
+    }
+
+    pub fn list_blocked_apps(&self) -> Result<Vec<BlockedApp>, AppError> {
+        let conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
+
+        let mut stmt = conn
+            .prepare("SELECT id, name, enabled FROM blocked_apps ORDER BY name")
+            .map_err(|e| AppError::database("failed to read blocked apps", e))?;
+        let rows = stmt
+            .query_map([], read_blocked_app)
+            .map_err(|e| AppError::database("failed to read blocked apps", e))?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::database("failed to read blocked apps", e))
+    }
+
+    pub fn add_blocked_app(&self, name: &str) -> Result<BlockedApp, AppError> {
+        let conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
+
+        conn.execute(
+            "INSERT INTO blocked_apps (name, enabled) VALUES (?1, 1)
+             ON CONFLICT(name) DO UPDATE SET enabled = 1",
+            params![name],
+        )
+        .map_err(|e| AppError::database("failed to add blocked app", e))?;
+
+        conn.query_row(
+            "SELECT id, name, enabled FROM blocked_apps WHERE name = ?1",
+            params![name],
+            read_blocked_app,
+        )
+        .map_err(|e| AppError::database("failed to read blocked app", e))
+    }
+
+    pub fn remove_blocked_app(&self, id: i64) -> Result<(), AppError> {
+        let conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
+
+        let removed = conn
+            .execute("DELETE FROM blocked_apps WHERE id = ?1", params![id])
+            .map_err(|e| AppError::database("failed to remove blocked app", e))?;
+        if removed == 0 {
+            return Err(AppError::not_found(format!("blocked app {id} not found")));
+        }
+        Ok(())
+    }
+
+    pub fn set_blocked_app_enabled(&self, id: i64, enabled: bool) -> Result<(), AppError> {
+        let conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
+
+        let updated = conn
+            .execute(
+                "UPDATE blocked_apps SET enabled = ?2 WHERE id = ?1",
+                params![id, enabled as i64],
+            )
+            .map_err(|e| AppError::database("failed to update blocked app", e))?;
+        if updated == 0 {
+            return Err(AppError::not_found(format!("blocked app {id} not found")));
+        }
+        Ok(())
+    }
+
+    pub fn list_block_schedules(&self) -> Result<Vec<BlockSchedule>, AppError> {
+        let conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, days, start_time, end_time, enabled FROM block_schedules
+                 ORDER BY start_time, id",
+            )
+            .map_err(|e| AppError::database("failed to read schedules", e))?;
+        let rows = stmt
+            .query_map([], read_block_schedule)
+            .map_err(|e| AppError::database("failed to read schedules", e))?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::database("failed to read schedules", e))
+    }
+
+    /// Stores an already-normalized schedule (see `schedule::normalize_schedule`).
+    pub fn add_block_schedule(
+        &self,
+        days: &[u8],
+        start: &str,
+        end: &str,
+    ) -> Result<BlockSchedule, AppError> {
+        let conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
+
+        let days = days.iter().map(u8::to_string).collect::<Vec<_>>().join(",");
+        conn.execute(
+            "INSERT INTO block_schedules (days, start_time, end_time, enabled)
+             VALUES (?1, ?2, ?3, 1)",
+            params![days, start, end],
+        )
+        .map_err(|e| AppError::database("failed to add schedule", e))?;
+
+        conn.query_row(
+            "SELECT id, days, start_time, end_time, enabled FROM block_schedules WHERE id = ?1",
+            params![conn.last_insert_rowid()],
+            read_block_schedule,
+        )
+        .map_err(|e| AppError::database("failed to read schedule", e))
+    }
+
+    pub fn remove_block_schedule(&self, id: i64) -> Result<(), AppError> {
+        let conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
+
+        let removed = conn
+            .execute("DELETE FROM block_schedules WHERE id = ?1", params![id])
+            .map_err(|e| AppError::database("failed to remove schedule", e))?;
+        if removed == 0 {
+            return Err(AppError::not_found(format!("schedule {id} not found")));
+        }
+        Ok(())
+    }
+
+    pub fn set_block_schedule_enabled(&self, id: i64, enabled: bool) -> Result<(), AppError> {
+        let conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
+
+        let updated = conn
+            .execute(
+                "UPDATE block_schedules SET enabled = ?2 WHERE id = ?1",
+                params![id, enabled as i64],
+            )
+            .map_err(|e| AppError::database("failed to update schedule", e))?;
+        if updated == 0 {
+            return Err(AppError::not_found(format!("schedule {id} not found")));
+        }
+        Ok(())
+    }
+
+    pub fn load_session_lock(&self) -> Result<Option<SessionLock>, AppError> {
+        let conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
+
+        let row: Option<(String, Option<String>)> = conn
+            .query_row(
+                "SELECT ends_at, unlock_requested_at FROM session_lock WHERE id = 1",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| AppError::database("failed to read session lock", e))?;
+
+        // An unreadable timestamp can only come from a hand-edited database; treat it as
+        // no lock rather than locking the user out.
+        Ok(row.and_then(|(ends_at, unlock_requested_at)| {
+            Some(SessionLock {
+                ends_at: parse_timestamp(&ends_at)?,
+                unlock_requested_at: unlock_requested_at.as_deref().and_then(parse_timestamp),
+            })
+        }))
+    }
+
+    pub fn save_session_lock(&self, lock: &SessionLock) -> Result<(), AppError> {
+        let conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
+
+        conn.execute(
+            "INSERT INTO session_lock (id, ends_at, unlock_requested_at) VALUES (1, ?1, ?2)
+             ON CONFLICT(id) DO UPDATE SET
+                ends_at = excluded.ends_at,
+                unlock_requested_at = excluded.unlock_requested_at",
+            params![
+                lock.ends_at.to_rfc3339(),
+                lock.unlock_requested_at.map(|at| at.to_rfc3339())
+            ],
+        )
+        .map_err(|e| AppError::database("failed to save session lock", e))?;
+        Ok(())
+    }
+
+    pub fn clear_session_lock(&self) -> Result<(), AppError> {
+        let conn = self.conn.lock().map_err(|_| AppError::poisoned("db"))?;
+
+        conn.execute("DELETE FROM session_lock", [])
+            .map_err(|e| AppError::database("failed to clear session lock", e))?;
+        Ok(())
+    }
+}
+
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|at| at.with_timezone(&Utc))
+}
+
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn read_profile(r: &rusqlite::Row<'_>) -> rusqlite::Result<BlocklistProfile> {
+    Ok(BlocklistProfile {
+        id: r.get(0)?,
+        name: r.get(1)?,
+        suppress_notifications: r.get::<_, i64>(2)? != 0,
+        site_count: r.get::<_, i64>(3)? as u32,
+    })
+}
+
+fn read_blocked_site(r: &rusqlite::Row<'_>) -> rusqlite::Result<BlockedSite> {
+    Ok(BlockedSite {
+        id: r.get(0)?,
+        profile_id: r.get(1)?,
+        domain: r.get(2)?,
+        enabled: r.get::<_, i64>(3)? != 0,
+    })
+}
+
+fn read_blocked_app(r: &rusqlite::Row<'_>) -> rusqlite::Result<BlockedApp> {
+    Ok(BlockedApp {
+        id: r.get(0)?,
+        name: r.get(1)?,
+        enabled: r.get::<_, i64>(2)? != 0,
+    })
+}
+
+fn read_block_schedule(r: &rusqlite::Row<'_>) -> rusqlite::Result<BlockSchedule> {
+    Ok(BlockSchedule {
+        id: r.get(0)?,
+        days: split_list(&r.get::<_, String>(1)?)
+            .iter()
+            .filter_map(|day| day.parse().ok())
+            .collect(),
+        start: r.get(2)?,
+        end: r.get(3)?,
+        enabled: r.get::<_, i64>(4)? != 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, FakeClock};
+
+    #[test]
+    fn statistics_cover_today_only() {
+        let clock = FakeClock::at("2024-01-01T10:00:00Z");
+        let db = Database::in_memory(clock.clone()).unwrap();
+
+        db.insert_completed_phase(Phase::Work, 25 * 60, clock.now())
+            .unwrap();
+        clock.advance_secs(5 * 60);
+        db.insert_completed_phase(Phase::ShortBreak, 5 * 60, clock.now())
+            .unwrap();
+        clock.advance_secs(15 * 60);
+        db.insert_completed_phase(Phase::LongBreak, 15 * 60, clock.now())
+            .unwrap();
+
+        let stats = db.get_today_statistics().unwrap();
+        assert_eq!(stats.date, "2024-01-01");
+        assert_eq!(stats.total_focus_minutes, 25);
+        assert_eq!(stats.work_sessions_completed, 1);
+        assert_eq!(stats.breaks_taken, 2);
+        assert_eq!(stats.short_breaks_taken, 1);
+        assert_eq!(stats.long_breaks_taken, 1);
+        assert_eq!(stats.interruptions, 0);
+        assert_eq!(stats.completion_rate, Some(1.0));
+        assert_eq!(stats.daily_goal, Settings::default().daily_goal);
+    }
+
+    #[test]
+    fn interrupted_work_counts_against_the_completion_rate() {
+        let clock = FakeClock::at("2024-01-01T10:00:00Z");
+        let db = Database::in_memory(clock.clone()).unwrap();
+        assert_eq!(db.get_today_statistics().unwrap().completion_rate, None);
+
+        db.insert_completed_phase(Phase::Work, 25 * 60, clock.now())
+            .unwrap();
+        clock.advance_secs(15 * 60);
+        db.insert_incomplete_phase(Phase::Work, 10 * 60, clock.now())
+            .unwrap();
+        // An abandoned break isn't an interruption of work.
+        db.insert_incomplete_phase(Phase::ShortBreak, 60, clock.now())
+            .unwrap();
+
+        let stats = db.get_today_statistics().unwrap();
+        assert_eq!(stats.work_sessions_completed, 1);
+        assert_eq!(stats.interruptions, 1);
+        assert_eq!(stats.completion_rate, Some(0.5));
+        assert_eq!(stats.total_focus_minutes, 35);
+        assert_eq!(stats.breaks_taken, 0);
+    }
+
+    #[test]
+    fn short_phases_add_up_to_whole_minutes() {
+        let clock = FakeClock::at("2024-01-01T10:00:00Z");
+        let db = Database::in_memory(clock.clone()).unwrap();
+        for _ in 0..2 {
+            db.insert_completed_phase(Phase::Work, 90, clock.now())
+                .unwrap();
+        }
+        assert_eq!(db.get_today_statistics().unwrap().total_focus_minutes, 3);
+    }
+
+    #[test]
+    fn minute_durations_are_migrated_to_seconds() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE sessions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                date TEXT NOT NULL,
+                start_time TEXT NOT NULL,
+                end_time TEXT NOT NULL,
+                duration_minutes INTEGER NOT NULL,
+                phase_type TEXT NOT NULL,
+                completed INTEGER NOT NULL DEFAULT 1,
+                notes TEXT
+            );
+            INSERT INTO sessions (date, start_time, end_time, duration_minutes, phase_type)
+            VALUES ('2024-01-01', '2024-01-01T09:58:30.250+00:00',
+                    '2024-01-01T10:00:00.250+00:00', 1, 'work'),
+                   ('2024-01-01', 'garbled', 'garbled', 2, 'work');",
+        )
+        .unwrap();
+
+        let db = Database::from_connection(conn, FakeClock::at("2024-01-01T12:00:00Z")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        let seconds: Vec<i64> = conn
+            .prepare("SELECT duration_seconds FROM sessions ORDER BY id")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        // Exact from the timestamps where they parse, otherwise from the old minutes.
+        assert_eq!(seconds, vec![90, 120]);
+        let version: i64 = conn
+            .pragma_query_value(None, "user_version", |r| r.get(0))
+            .unwrap();
+        assert_eq!(version, migrations::latest_version());
+    }
+
+    #[test]
+    fn statistics_start_over_at_midnight() {
+        let clock = FakeClock::at("2024-01-01T23:00:00Z");
+        let db = Database::in_memory(clock.clone()).unwrap();
+        for _ in 0..2 {
+            clock.advance_secs(25 * 60);
+            db.insert_completed_phase(Phase::Work, 25 * 60, clock.now())
+                .unwrap();
+        }
+        assert_eq!(
+            db.get_today_statistics().unwrap().work_sessions_completed,
+            2
+        );
+
+        // 00:10 the next day.
+        clock.advance_secs(20 * 60);
+        let stats = db.get_today_statistics().unwrap();
+        assert_eq!(stats.date, "2024-01-02");
+        assert_eq!(stats.work_sessions_completed, 0);
+        assert_eq!(stats.total_focus_minutes, 0);
+        assert_eq!(stats.longest_streak, 2);
+
+        db.insert_completed_phase(Phase::Work, 25 * 60, clock.now())
+            .unwrap();
+        let stats = db.get_today_statistics().unwrap();
+        assert_eq!(stats.work_sessions_completed, 1);
+        assert_eq!(stats.longest_streak, 2);
+    }
 }
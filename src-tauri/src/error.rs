@@ -0,0 +1,178 @@
+use std::fmt;
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+/// Error returned by every command. Serialized as `{ "code": ..., "message": ... }` (plus
+/// `field` for validation errors) so the UI can offer a fix that matches the cause.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum AppError {
+    /// The OS refused access, e.g. writing the hosts file without admin/root or binding a
+    /// privileged port.
+    PermissionDenied {
+        message: String,
+    },
+    /// A shared lock was poisoned by a panic on another thread.
+    MutexPoisoned {
+        message: String,
+    },
+    /// SQLite is busy or locked by another process.
+    DatabaseLocked {
+        message: String,
+    },
+    Database {
+        message: String,
+    },
+    /// A user-supplied value was rejected. `field` is the settings/command field it came from.
+    Validation {
+        field: String,
+        message: String,
+    },
+    NotFound {
+        message: String,
+    },
+    /// The request is valid but conflicts with the current state (e.g. deleting the last
+    /// blocklist profile).
+    Conflict {
+        message: String,
+    },
+    /// The hosts file's focus-timer section is damaged and must be restored first.
+    HostsRecoveryNeeded {
+        message: String,
+    },
+    /// Refused because a locked work session is running.
+    SessionLocked {
+        message: String,
+    },
+    /// Not available on this platform or desktop.
+    Unsupported {
+        message: String,
+    },
+    Io {
+        message: String,
+    },
+}
+
+impl AppError {
+    pub fn poisoned(what: &str) -> Self {
+        AppError::MutexPoisoned {
+            message: format!("{what} mutex poisoned"),
+        }
+    }
+
+    /// A failed SQLite call, keeping "busy/locked" apart so the UI can suggest a retry.
+    pub fn database(context: &str, e: rusqlite::Error) -> Self {
+        let message = format!("{context}: {e}");
+        match e.sqlite_error_code() {
+            Some(rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked) => {
+                AppError::DatabaseLocked { message }
+            }
+            _ => AppError::Database { message },
+        }
+    }
+
+    /// A failed OS call; permission errors get their own code.
+    pub fn io(context: impl fmt::Display, e: io::Error) -> Self {
+        let message = format!("{context}: {e}");
+        match e.kind() {
+            io::ErrorKind::PermissionDenied => AppError::PermissionDenied { message },
+            _ => AppError::Io { message },
+        }
+    }
+
+    pub fn validation(field: &str, message: impl Into<String>) -> Self {
+        AppError::Validation {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        AppError::NotFound {
+            message: message.into(),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::PermissionDenied { message }
+            | AppError::MutexPoisoned { message }
+            | AppError::DatabaseLocked { message }
+            | AppError::Database { message }
+            | AppError::Validation { message, .. }
+            | AppError::NotFound { message }
+            | AppError::Conflict { message }
+            | AppError::HostsRecoveryNeeded { message }
+            | AppError::SessionLocked { message }
+            | AppError::Unsupported { message }
+            | AppError::Io { message } => message,
+        }
+    }
+
+    /// Same error with `context` in front of the message, e.g. to say which step failed.
+    pub fn context(mut self, context: impl fmt::Display) -> Self {
+        let prefixed = format!("{context}: {}", self.message());
+        match &mut self {
+            AppError::PermissionDenied { message }
+            | AppError::MutexPoisoned { message }
+            | AppError::DatabaseLocked { message }
+            | AppError::Database { message }
+            | AppError::Validation { message, .. }
+            | AppError::NotFound { message }
+            | AppError::Conflict { message }
+            | AppError::HostsRecoveryNeeded { message }
+            | AppError::SessionLocked { message }
+            | AppError::Unsupported { message }
+            | AppError::Io { message } => *message = prefixed,
+        }
+        self
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_with_a_code() {
+        let json = serde_json::to_value(AppError::validation("workMinutes", "too long")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "code": "validation",
+                "field": "workMinutes",
+                "message": "too long",
+            })
+        );
+
+        let denied = AppError::io(
+            "failed to write /etc/hosts",
+            io::ErrorKind::PermissionDenied.into(),
+        );
+        assert_eq!(
+            serde_json::to_value(&denied).unwrap()["code"],
+            "permission_denied"
+        );
+    }
+
+    #[test]
+    fn busy_database_is_reported_as_locked() {
+        let busy = rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+            None,
+        );
+        assert!(matches!(
+            AppError::database("failed to save settings", busy),
+            AppError::DatabaseLocked { .. }
+        ));
+    }
+}
@@ -1,169 +1,302 @@
-use std::fs;
-use std::path::{Path, PathBuf};
+#[cfg(target_os = "linux")]
+mod dnd;
+mod dns;
+mod hosts;
+mod journal;
+mod proxy;
+
+use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 
 use serde::Serialize;
-use tauri::{AppHandle, Emitter};
 
-#[derive(Debug, Clone, Serialize)]
+use crate::error::AppError;
+use crate::models::BlockingMethod;
+use crate::process_blocker;
+
+use dns::DnsSinkhole;
+#[cfg(test)]
+pub use hosts::MemoryHostsStore;
+pub use hosts::{elevation_hint, DirectHosts, HostsBackend, HostsRecovery};
+pub use journal::Journal;
+use journal::JournalEntry;
+use proxy::BlockProxy;
+pub use proxy::TimeLeft;
+
+/// What focus-mode blocking is currently doing; sent to the UI as `blocker-status`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct BlockingErrorEvent {
-    pub message: String,
+pub struct BlockerStatus {
+    pub enabled: bool,
+    pub backend: BlockingMethod,
+    pub allowlist: bool,
+    /// Sites in the enforced list (blocked ones, or allowed ones in allowlist mode).
+    pub domain_count: usize,
+    pub notifications: NotificationState,
+    pub last_error: Option<AppError>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationState {
+    /// The profile doesn't ask for notifications to be silenced (or blocking is off).
+    #[default]
+    Untouched,
+    Suppressed,
+    /// Suppression was requested but this desktop offers no way to do it.
+    Unavailable,
 }
 
+/// Called with the new status whenever `enable`/`disable` change it.
+pub type StatusListener = Arc<dyn Fn(&BlockerStatus) + Send + Sync>;
+
 #[derive(Clone, Default)]
 pub struct FocusBlocker {
     inner: Arc<Mutex<Inner>>,
 }
 
+/// Mechanism used to enforce the blocklist while a work session runs.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum BlockMethod {
+    #[default]
+    Hosts,
+    Dns {
+        listen: SocketAddr,
+        upstream: SocketAddr,
+    },
+    Proxy {
+        listen: SocketAddr,
+    },
+}
+
 #[derive(Default)]
 struct Inner {
     enabled: bool,
+    method: BlockMethod,
+    hosts: Box<dyn HostsBackend>,
+    dns: Option<DnsSinkhole>,
+    proxy: Option<BlockProxy>,
+    /// Shown on the proxy's "blocked" page.
+    time_left: Option<TimeLeft>,
+    pending_recovery: Option<HostsRecovery>,
+    toast_suppressed: bool,
     toast_prev: Option<u32>,
     toast_prev_existed: bool,
+    #[cfg(target_os = "linux")]
+    dnd: Option<dnd::Suppression>,
+    journal: Journal,
+    /// Size of the list last applied, for [`BlockerStatus::domain_count`].
+    domain_count: usize,
+    allowlist: bool,
+    last_error: Option<AppError>,
+    status_listener: Option<StatusListener>,
 }
 
-const HOSTS_START: &str = "# focus-timer-block-start";
-const HOSTS_END: &str = "# focus-timer-block-end";
-
-fn blocked_domains() -> &'static [&'static str] {
-    &[
-        "facebook.com",
-        "www.facebook.com",
-        "m.facebook.com",
-        "messenger.com",
-        "www.messenger.com",
-        "youtube.com",
-        "www.youtube.com",
-        "m.youtube.com",
-        "youtu.be",
-        "twitter.com",
-        "www.twitter.com",
-        "x.com",
-        "www.x.com",
-        "instagram.com",
-        "www.instagram.com",
-        "reddit.com",
-        "www.reddit.com",
-        "old.reddit.com",
-    ]
+/// What a work session should enforce, resolved from the active blocklist profile.
+#[derive(Debug, Clone)]
+pub struct ActiveBlocklist {
+    pub domains: Vec<String>,
+    /// Subdomain labels (`www`, `m`, ...) added in front of every domain in the hosts block.
+    pub subdomains: Vec<String>,
+    pub suppress_notifications: bool,
+    /// Treat `domains` as the only sites allowed during work instead of the blocked ones.
+    pub allowlist: bool,
 }
 
-fn hosts_path() -> PathBuf {
-    let system_root = std::env::var("SystemRoot").unwrap_or_else(|_| "C:\\Windows".to_string());
-    PathBuf::from(system_root)
-        .join("System32")
-        .join("drivers")
-        .join("etc")
-        .join("hosts")
-}
+impl ActiveBlocklist {
+    pub fn rules(&self) -> SiteRules {
+        SiteRules {
+            domains: self.domains.clone(),
+            allowlist: self.allowlist,
+        }
+    }
 
-fn detect_newline(text: &str) -> &'static str {
-    if text.contains("\r\n") {
-        "\r\n"
-    } else {
-        "\n"
+    /// Every host name that should be sinkholed, with subdomain variants expanded.
+    pub fn hosts(&self) -> Vec<String> {
+        let mut out: Vec<String> = Vec::new();
+        for domain in &self.domains {
+            for host in expand_domain(domain, &self.subdomains) {
+                if !out.contains(&host) {
+                    out.push(host);
+                }
+            }
+        }
+        out
     }
 }
 
-fn strip_hosts_block(existing: &str) -> String {
-    if !existing.contains(HOSTS_START) {
-        return existing.to_string();
+/// What the DNS sinkhole and the proxy check each requested host name against.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SiteRules {
+    pub domains: Vec<String>,
+    /// Block every host *except* `domains` (and their subdomains).
+    pub allowlist: bool,
+}
+
+impl SiteRules {
+    pub fn is_blocked(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        if self.allowlist && host == "localhost" {
+            return false;
+        }
+        matches_domains(&host, &self.domains) != self.allowlist
     }
+}
 
-    let newline = detect_newline(existing);
+/// Seeded into the `blocked_sites` table the first time the database is created.
+/// Common variants like `www.` and `m.` come from the subdomain expansion setting.
+pub const DEFAULT_BLOCKED_DOMAINS: &[&str] = &[
+    "facebook.com",
+    "messenger.com",
+    "youtube.com",
+    "youtu.be",
+    "twitter.com",
+    "x.com",
+    "instagram.com",
+    "reddit.com",
+];
 
-    let mut out = String::with_capacity(existing.len());
-    let mut skipping = false;
+pub const DEFAULT_SUBDOMAINS: &[&str] = &["www", "m", "mobile", "web", "old", "new", "music"];
 
-    for line in existing.lines() {
-        if line.trim_end() == HOSTS_START {
-            skipping = true;
-            continue;
-        }
-        if skipping {
-            if line.trim_end() == HOSTS_END {
-                skipping = false;
-            }
-            continue;
-        }
-        out.push_str(line);
-        out.push_str(newline);
-    }
+fn is_valid_label(label: &str) -> bool {
+    !label.is_empty()
+        && label.len() <= 63
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+        && label
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+}
 
-    out
+/// Validates a single subdomain label for the expansion list.
+pub fn normalize_subdomain(input: &str) -> Result<String, AppError> {
+    let label = input.trim().trim_end_matches('.').to_ascii_lowercase();
+    if is_valid_label(&label) {
+        Ok(label)
+    } else {
+        Err(AppError::validation(
+            "blockSubdomains",
+            format!("\"{}\" is not a valid subdomain label", input.trim()),
+        ))
+    }
 }
 
-fn append_hosts_block(existing: &str) -> String {
-    let cleaned = strip_hosts_block(existing);
-    let newline = detect_newline(existing);
+/// Turns user input such as `https://www.YouTube.com/feed` into `youtube.com`, rejecting
+/// anything that isn't a plain DNS host name (IP addresses, wildcards, bad characters).
+pub fn normalize_domain(input: &str) -> Result<String, AppError> {
+    let invalid = || {
+        AppError::validation(
+            "domain",
+            format!(
+                "\"{}\" is not a valid domain like example.com",
+                input.trim()
+            ),
+        )
+    };
 
-    let mut out = cleaned;
+    let mut host = input.trim().to_ascii_lowercase();
+    if let Some(idx) = host.find("://") {
+        host.drain(..idx + 3);
+    }
+    if let Some(idx) = host.find(['/', '?', '#']) {
+        host.truncate(idx);
+    }
+    if let Some(idx) = host.rfind(':') {
+        if host[idx + 1..].bytes().all(|b| b.is_ascii_digit()) {
+            host.truncate(idx);
+        }
+    }
+    let host = host.trim_end_matches('.');
+    // `www.com` is a domain of its own, not `com` with a www prefix.
+    let host = match host.strip_prefix("www.") {
+        Some(rest) if rest.contains('.') => rest,
+        _ => host,
+    };
 
-    if !out.ends_with('\n') && !out.is_empty() {
-        out.push_str(newline);
+    if is_valid_hostname(host) {
+        Ok(host.to_string())
+    } else {
+        Err(invalid())
     }
+}
 
-    out.push_str(HOSTS_START);
-    out.push_str(newline);
-    for domain in blocked_domains() {
-        out.push_str("0.0.0.0 ");
-        out.push_str(domain);
-        out.push_str(newline);
+/// True for a lowercase, multi-label DNS host name that is safe to write into a hosts file.
+pub fn is_valid_hostname(host: &str) -> bool {
+    if host.len() > 253 {
+        return false;
     }
-    out.push_str(HOSTS_END);
-    out.push_str(newline);
-    out
+    let labels: Vec<&str> = host.split('.').collect();
+    if labels.len() < 2 || !labels.iter().all(|l| is_valid_label(l)) {
+        return false;
+    }
+    // A numeric top-level label means this is an IPv4 address, not a domain.
+    !labels
+        .last()
+        .is_some_and(|tld| tld.bytes().all(|b| b.is_ascii_digit()))
 }
 
-fn read_to_string(path: &Path) -> Result<String, String> {
-    fs::read_to_string(path)
-        .map_err(|e| format!("failed to read {path:?}: {e}"))
+/// `youtube.com` + `["www", "m"]` -> `youtube.com`, `www.youtube.com`, `m.youtube.com`.
+pub fn expand_domain(domain: &str, subdomains: &[String]) -> Vec<String> {
+    let mut out = Vec::with_capacity(subdomains.len() + 1);
+    out.push(domain.to_string());
+    for sub in subdomains {
+        let host = format!("{sub}.{domain}");
+        if host.len() <= 253 && !out.contains(&host) {
+            out.push(host);
+        }
+    }
+    out
 }
 
-fn write_string(path: &Path, contents: &str) -> Result<(), String> {
-    fs::write(path, contents).map_err(|e| format!("failed to write {path:?}: {e}"))
+/// True if `host` equals one of `domains` or is any subdomain of one.
+fn matches_domains(host: &str, domains: &[String]) -> bool {
+    domains.iter().any(|domain| {
+        host == domain
+            || host
+                .strip_suffix(domain.as_str())
+                .is_some_and(|prefix| prefix.ends_with('.'))
+    })
 }
 
 #[cfg(target_os = "windows")]
-fn set_toast_enabled(enabled: bool) -> Result<(Option<u32>, bool), String> {
+fn set_toast_enabled(enabled: bool) -> Result<(Option<u32>, bool), AppError> {
     use winreg::enums::HKEY_CURRENT_USER;
     use winreg::RegKey;
 
     let hkcu = RegKey::predef(HKEY_CURRENT_USER);
     let (key, _) = hkcu
         .create_subkey("Software\\Microsoft\\Windows\\CurrentVersion\\PushNotifications")
-        .map_err(|e| format!("failed to open PushNotifications registry key: {e}"))?;
+        .map_err(|e| AppError::io("failed to open PushNotifications registry key", e))?;
 
     let existed = key.get_raw_value("ToastEnabled").is_ok();
     let prev = key.get_value::<u32, _>("ToastEnabled").ok();
 
     let value: u32 = if enabled { 1 } else { 0 };
     key.set_value("ToastEnabled", &value)
-        .map_err(|e| format!("failed to set ToastEnabled registry value: {e}"))?;
+        .map_err(|e| AppError::io("failed to set ToastEnabled registry value", e))?;
 
     Ok((prev, existed))
 }
 
 #[cfg(not(target_os = "windows"))]
-fn set_toast_enabled(_enabled: bool) -> Result<(Option<u32>, bool), String> {
+fn set_toast_enabled(_enabled: bool) -> Result<(Option<u32>, bool), AppError> {
     Ok((None, false))
 }
 
 #[cfg(target_os = "windows")]
-fn restore_toast_enabled(prev: Option<u32>, existed: bool) -> Result<(), String> {
+fn restore_toast_enabled(prev: Option<u32>, existed: bool) -> Result<(), AppError> {
     use winreg::enums::HKEY_CURRENT_USER;
     use winreg::RegKey;
 
     let hkcu = RegKey::predef(HKEY_CURRENT_USER);
     let (key, _) = hkcu
         .create_subkey("Software\\Microsoft\\Windows\\CurrentVersion\\PushNotifications")
-        .map_err(|e| format!("failed to open PushNotifications registry key: {e}"))?;
+        .map_err(|e| AppError::io("failed to open PushNotifications registry key", e))?;
 
     if existed {
         if let Some(v) = prev {
             key.set_value("ToastEnabled", &v)
-                .map_err(|e| format!("failed to restore ToastEnabled registry value: {e}"))?;
+                .map_err(|e| AppError::io("failed to restore ToastEnabled registry value", e))?;
         }
     } else {
         let _ = key.delete_value("ToastEnabled");
@@ -173,88 +306,817 @@ fn restore_toast_enabled(prev: Option<u32>, existed: bool) -> Result<(), String>
 }
 
 #[cfg(not(target_os = "windows"))]
-fn restore_toast_enabled(_prev: Option<u32>, _existed: bool) -> Result<(), String> {
+fn restore_toast_enabled(_prev: Option<u32>, _existed: bool) -> Result<(), AppError> {
     Ok(())
 }
 
-impl FocusBlocker {
-    pub fn ensure_disabled(&self) {
-        let _ = self.disable();
+impl BlockMethod {
+    fn kind(&self) -> BlockingMethod {
+        match self {
+            BlockMethod::Hosts => BlockingMethod::Hosts,
+            BlockMethod::Dns { .. } => BlockingMethod::Dns,
+            BlockMethod::Proxy { .. } => BlockingMethod::Proxy,
+        }
     }
+}
 
-    pub fn enable(&self) -> Result<(), String> {
-        let mut inner = self
-            .inner
-            .lock()
-            .map_err(|_| "focus blocker mutex poisoned".to_string())?;
+impl Inner {
+    fn apply_blocklist(&mut self, blocklist: &ActiveBlocklist) -> Result<(), AppError> {
+        match self.method {
+            // A hosts file can only list names to block, not "everything else".
+            BlockMethod::Hosts if blocklist.allowlist => Err(AppError::validation(
+                "blockMode",
+                "allowlist mode needs the DNS or proxy blocking method",
+            )),
+            BlockMethod::Hosts => {
+                self.hosts.apply(&blocklist.hosts())?;
+                if let Some(backup) = self.hosts.last_backup() {
+                    self.record(|entry| entry.hosts_backup = Some(backup.display().to_string()));
+                }
+                Ok(())
+            }
+            // The resolver matches subdomains itself, so no expansion is needed.
+            BlockMethod::Dns { .. } | BlockMethod::Proxy { .. } => {
+                self.start_listener(blocklist.rules())
+            }
+        }
+    }
 
-        if inner.enabled {
+    fn clear_blocklist(&mut self) -> Result<(), AppError> {
+        match self.method {
+            BlockMethod::Hosts => {
+                self.hosts.clear()?;
+                self.record(|entry| entry.hosts_backup = None);
+                Ok(())
+            }
+            // Keep answering: the system or browser is pointed at the sinkhole or proxy even
+            // between sessions, so it goes on passing everything through.
+            BlockMethod::Dns { .. } => {
+                if let Some(dns) = &self.dns {
+                    dns.set_rules(SiteRules::default());
+                }
+                Ok(())
+            }
+            BlockMethod::Proxy { .. } => {
+                if let Some(proxy) = &self.proxy {
+                    proxy.set_rules(SiteRules::default());
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Starts the DNS sinkhole or proxy (whichever is selected) with `rules`, or swaps them in
+    /// if it's already running.
+    fn start_listener(&mut self, rules: SiteRules) -> Result<(), AppError> {
+        match self.method {
+            BlockMethod::Hosts => {}
+            BlockMethod::Dns { listen, upstream } => match &self.dns {
+                Some(dns) => dns.set_rules(rules),
+                None => self.dns = Some(DnsSinkhole::start(listen, upstream, rules)?),
+            },
+            BlockMethod::Proxy { listen } => match &self.proxy {
+                Some(proxy) => proxy.set_rules(rules),
+                None => {
+                    self.proxy = Some(BlockProxy::start(listen, rules, self.time_left.clone())?)
+                }
+            },
+        }
+        Ok(())
+    }
+
+    fn stop_listeners(&mut self) {
+        if let Some(dns) = self.dns.take() {
+            dns.stop();
+        }
+        if let Some(proxy) = self.proxy.take() {
+            proxy.stop();
+        }
+    }
+
+    fn suppress_toasts(&mut self) -> Result<(), AppError> {
+        // Not every Linux desktop has a Do Not Disturb switch we can reach; that shouldn't
+        // stop the sites from being blocked.
+        #[cfg(target_os = "linux")]
+        if self.dnd.is_none() {
+            self.dnd = dnd::suppress().ok();
+        }
+        let (prev, existed) = set_toast_enabled(false)?;
+        self.toast_prev = prev;
+        self.toast_prev_existed = existed;
+        self.toast_suppressed = true;
+
+        #[cfg(target_os = "linux")]
+        let gnome_banners_prev = match &self.dnd {
+            Some(dnd::Suppression::GnomeBanners { was_shown }) => Some(*was_shown),
+            _ => None,
+        };
+        #[cfg(not(target_os = "linux"))]
+        let gnome_banners_prev = None;
+        self.record(|entry| {
+            entry.toast_suppressed = true;
+            entry.toast_prev = prev;
+            entry.toast_prev_existed = existed;
+            entry.gnome_banners_prev = gnome_banners_prev;
+        });
+        Ok(())
+    }
+
+    fn restore_toasts(&mut self) -> Result<(), AppError> {
+        if !self.toast_suppressed {
+            return Ok(());
+        }
+        // Best effort: if Do Not Disturb can't be lifted (the notification server went away,
+        // say) there's nothing left to retry, so report it without keeping the sites blocked.
+        #[cfg(target_os = "linux")]
+        let dnd_result = match self.dnd.take() {
+            Some(suppression) => dnd::restore(&suppression)
+                .map_err(|e| e.context("couldn't turn Do Not Disturb back off")),
+            None => Ok(()),
+        };
+        #[cfg(not(target_os = "linux"))]
+        let dnd_result = Ok(());
+        restore_toast_enabled(self.toast_prev, self.toast_prev_existed)?;
+        self.toast_prev = None;
+        self.toast_prev_existed = false;
+        self.toast_suppressed = false;
+        self.record(|entry| {
+            entry.toast_suppressed = false;
+            entry.toast_prev = None;
+            entry.toast_prev_existed = false;
+            entry.gnome_banners_prev = None;
+        });
+        dnd_result
+    }
+
+    fn enable(&mut self, blocklist: &ActiveBlocklist) -> Result<(), AppError> {
+        if let (BlockMethod::Hosts, Some(recovery)) = (&self.method, &self.pending_recovery) {
+            return Err(AppError::HostsRecoveryNeeded {
+                message: format!(
+                    "the hosts file needs recovery before blocking can start: {}",
+                    recovery.reason
+                ),
+            });
+        }
+
+        if self.enabled {
+            // Already blocking (e.g. the profile or its sites changed): reconcile in place.
+            if !blocklist.suppress_notifications && self.toast_suppressed {
+                self.restore_toasts()?;
+            } else if blocklist.suppress_notifications && !self.toast_suppressed {
+                self.suppress_toasts()?;
+            }
+            self.apply_blocklist(blocklist)?;
+            self.domain_count = blocklist.domains.len();
+            self.allowlist = blocklist.allowlist;
             return Ok(());
         }
 
         // Suppress toasts (best effort) to reduce mail/message notifications.
-        let (prev, existed) = set_toast_enabled(false)?;
-        inner.toast_prev = prev;
-        inner.toast_prev_existed = existed;
-
-        // Block social sites via hosts file. This requires admin on Windows.
-        let path = hosts_path();
-        match read_to_string(&path)
-            .map(|existing| append_hosts_block(&existing))
-            .and_then(|updated| write_string(&path, &updated))
-        {
-            Ok(()) => {}
-            Err(e) => {
-                // Don't leave system notifications disabled if we couldn't enforce blocking.
-                let _ = restore_toast_enabled(inner.toast_prev, inner.toast_prev_existed);
-                inner.toast_prev = None;
-                inner.toast_prev_existed = false;
-                return Err(e);
+        if blocklist.suppress_notifications {
+            self.suppress_toasts()?;
+        }
+
+        if let Err(e) = self.apply_blocklist(blocklist) {
+            // Don't leave system notifications disabled if we couldn't enforce blocking.
+            let _ = self.restore_toasts();
+            return Err(e);
+        }
+
+        self.enabled = true;
+        self.domain_count = blocklist.domains.len();
+        self.allowlist = blocklist.allowlist;
+        Ok(())
+    }
+
+    fn disable(&mut self) -> Result<(), AppError> {
+        if !self.enabled {
+            // Still try to strip any leftover hosts section, unless it's waiting for recovery.
+            if self.pending_recovery.is_none() {
+                if let Err(e) = self.hosts.clear() {
+                    // A damaged block turned up after startup: offer recovery like at startup.
+                    self.pending_recovery = self.hosts.recover().ok().flatten();
+                    return Err(match &self.pending_recovery {
+                        Some(recovery) => AppError::HostsRecoveryNeeded {
+                            message: format!("the hosts file needs recovery: {}", recovery.reason),
+                        },
+                        None => e,
+                    });
+                }
             }
+            return Ok(());
         }
 
-        inner.enabled = true;
+        // Restore notifications first so we don't keep the system muted on partial failures.
+        let restored = self.restore_toasts();
+        // Only a failed Windows toast restore leaves this set; keep blocking so it's retried.
+        if restored.is_err() && self.toast_suppressed {
+            return restored;
+        }
+        self.clear_blocklist()?;
+
+        self.enabled = false;
+        restored
+    }
+
+    fn notification_state(&self) -> NotificationState {
+        if !self.toast_suppressed {
+            return NotificationState::Untouched;
+        }
+        #[cfg(target_os = "linux")]
+        let suppressed = self.dnd.is_some();
+        #[cfg(target_os = "windows")]
+        let suppressed = true;
+        #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+        let suppressed = false;
+        if suppressed {
+            NotificationState::Suppressed
+        } else {
+            NotificationState::Unavailable
+        }
+    }
+
+    fn status(&self) -> BlockerStatus {
+        BlockerStatus {
+            enabled: self.enabled,
+            backend: self.method.kind(),
+            allowlist: self.enabled && self.allowlist,
+            domain_count: if self.enabled { self.domain_count } else { 0 },
+            notifications: self.notification_state(),
+            last_error: self.last_error.clone(),
+        }
+    }
+
+    /// Stores the outcome of an enable/disable and tells the listener.
+    fn publish<T>(&mut self, result: Result<T, AppError>) -> Result<T, AppError> {
+        self.last_error = result.as_ref().err().cloned();
+        if let Some(listener) = &self.status_listener {
+            listener(&self.status());
+        }
+        result
+    }
+
+    /// Best effort: the journal only matters if we crash, so a failed write must not stop
+    /// blocking or restoring.
+    fn record(&self, change: impl FnOnce(&mut JournalEntry)) {
+        let _ = self.journal.update(change);
+    }
+}
+
+/// Puts back what a previous run recorded but never restored. Returns the parts that
+/// still couldn't be restored so they stay in the journal for the next attempt.
+fn replay_journal(leftover: &JournalEntry) -> JournalEntry {
+    let mut unrestored = JournalEntry::default();
+
+    if leftover.toast_suppressed
+        && restore_toast_enabled(leftover.toast_prev, leftover.toast_prev_existed).is_err()
+    {
+        unrestored.toast_suppressed = true;
+        unrestored.toast_prev = leftover.toast_prev;
+        unrestored.toast_prev_existed = leftover.toast_prev_existed;
+    }
+
+    #[cfg(target_os = "linux")]
+    if let Some(was_shown) = leftover.gnome_banners_prev {
+        if dnd::restore(&dnd::Suppression::GnomeBanners { was_shown }).is_err() {
+            unrestored.gnome_banners_prev = Some(was_shown);
+        }
+    }
+
+    unrestored.suspended_processes =
+        process_blocker::resume_processes(&leftover.suspended_processes);
+    unrestored
+}
+
+impl FocusBlocker {
+    #[cfg(test)]
+    pub fn with_hosts_backend(hosts: impl HostsBackend + 'static) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                hosts: Box::new(hosts),
+                ..Inner::default()
+            })),
+        }
+    }
+
+    /// Called at startup: undoes anything a previous run left behind. A damaged hosts block
+    /// is not touched; it is reported through [`FocusBlocker::hosts_recovery`] instead.
+    ///
+    /// Whatever the journal says a crashed run changed (notifications, frozen apps) is
+    /// restored first.
+    pub fn ensure_disabled(&self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            let _ = inner.restore_toasts();
+            let leftover = inner.journal.take();
+            let mut unrestored = replay_journal(&leftover);
+
+            inner.enabled = false;
+            inner.pending_recovery = inner.hosts.recover().ok().flatten();
+            if let Some(recovery) = &mut inner.pending_recovery {
+                // Offer the copy taken right before the crashed session's block, not just
+                // the newest one.
+                if let Some(backup) = leftover.hosts_backup {
+                    recovery.backup_path = Some(backup.clone());
+                    unrestored.hosts_backup = Some(backup);
+                }
+            }
+            inner.record(|entry| *entry = unrestored);
+        }
+    }
+
+    /// Where pre-focus state is recorded so [`FocusBlocker::ensure_disabled`] can restore it
+    /// after a crash.
+    pub fn set_journal(&self, journal: Journal) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.journal = journal;
+        }
+    }
+
+    pub fn hosts_recovery(&self) -> Option<HostsRecovery> {
+        self.inner
+            .lock()
+            .ok()
+            .and_then(|inner| inner.pending_recovery.clone())
+    }
+
+    pub fn restore_hosts_backup(&self) -> Result<(), AppError> {
+        let mut inner = self
+            .inner
+            .lock()
+            .map_err(|_| AppError::poisoned("focus blocker"))?;
+        let backup = inner
+            .pending_recovery
+            .as_ref()
+            .and_then(|recovery| recovery.backup_path.clone());
+        inner
+            .hosts
+            .restore_backup(backup.as_deref().map(std::path::Path::new))?;
+        inner.pending_recovery = None;
+        inner.record(|entry| entry.hosts_backup = None);
         Ok(())
     }
 
-    pub fn disable(&self) -> Result<(), String> {
+    pub fn enable(&self, blocklist: &ActiveBlocklist) -> Result<(), AppError> {
         let mut inner = self
             .inner
             .lock()
-            .map_err(|_| "focus blocker mutex poisoned".to_string())?;
-
-        if !inner.enabled {
-            // Still try to strip any leftover hosts section if present.
-            let path = hosts_path();
-            if let Ok(existing) = read_to_string(&path) {
-                if existing.contains(HOSTS_START) {
-                    let updated = strip_hosts_block(&existing);
-                    let _ = write_string(&path, &updated);
+            .map_err(|_| AppError::poisoned("focus blocker"))?;
+        let result = inner
+            .enable(blocklist)
+            .map_err(|e| match (&inner.method, e) {
+                // Hosts file edits need admin/root (or the helper); DNS and proxy only need their port.
+                (BlockMethod::Hosts, AppError::PermissionDenied { message }) => {
+                    AppError::PermissionDenied {
+                        message: format!("{message} ({})", elevation_hint()),
+                    }
                 }
-            }
+                (_, e) => e,
+            });
+        inner.publish(result)
+    }
+
+    /// Swaps how the hosts file is written. An active block is cleared through the old
+    /// backend first; callers re-apply it with [`FocusBlocker::enable`].
+    pub fn set_hosts_backend(&self, backend: Box<dyn HostsBackend>) -> Result<(), AppError> {
+        let mut inner = self
+            .inner
+            .lock()
+            .map_err(|_| AppError::poisoned("focus blocker"))?;
+        if inner.enabled && inner.method == BlockMethod::Hosts {
+            let _ = inner.hosts.clear();
+        }
+        inner.hosts = backend;
+        Ok(())
+    }
+
+    /// Where the proxy's "blocked" page reads the remaining session time from.
+    pub fn set_time_left(&self, time_left: TimeLeft) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.time_left = Some(time_left);
+        }
+    }
+
+    /// Switches between the hosts file, the DNS sinkhole and the proxy. Like
+    /// [`FocusBlocker::set_hosts_backend`], an active block is lifted and must be re-applied.
+    ///
+    /// The DNS sinkhole and the proxy run for as long as their method is selected, blocking
+    /// nothing outside work sessions, since all lookups or traffic go through them.
+    pub fn set_block_method(&self, method: BlockMethod) -> Result<(), AppError> {
+        let mut inner = self
+            .inner
+            .lock()
+            .map_err(|_| AppError::poisoned("focus blocker"))?;
+        if inner.method == method {
             return Ok(());
         }
+        if inner.enabled {
+            let _ = inner.clear_blocklist();
+        }
+        inner.stop_listeners();
+        inner.method = method;
+        let result = inner.start_listener(SiteRules::default());
+        if result.is_err() {
+            return inner.publish(result);
+        }
+        Ok(())
+    }
 
-        // Restore notifications first so we don't keep the system muted on partial failures.
-        restore_toast_enabled(inner.toast_prev, inner.toast_prev_existed)?;
-        inner.toast_prev = None;
-        inner.toast_prev_existed = false;
+    pub fn is_enabled(&self) -> bool {
+        self.inner
+            .lock()
+            .map(|inner| inner.enabled)
+            .unwrap_or(false)
+    }
 
-        let path = hosts_path();
-        let existing = read_to_string(&path)?;
-        let updated = strip_hosts_block(&existing);
-        write_string(&path, &updated)?;
+    pub fn disable(&self) -> Result<(), AppError> {
+        let mut inner = self
+            .inner
+            .lock()
+            .map_err(|_| AppError::poisoned("focus blocker"))?;
+        let result = inner.disable();
+        inner.publish(result)
+    }
 
-        inner.enabled = false;
-        Ok(())
+    pub fn status(&self) -> Result<BlockerStatus, AppError> {
+        self.inner
+            .lock()
+            .map(|inner| inner.status())
+            .map_err(|_| AppError::poisoned("focus blocker"))
+    }
+
+    /// Surfaces a failure from outside the blocker (loading the blocklist, app blocking)
+    /// through the same status as its own errors.
+    pub fn report_error(&self, error: AppError) {
+        if let Ok(mut inner) = self.inner.lock() {
+            let _ = inner.publish::<()>(Err(error));
+        }
+    }
+
+    pub fn set_status_listener(&self, listener: StatusListener) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.status_listener = Some(listener);
+        }
     }
 }
 
-pub fn emit_blocking_error(app: &AppHandle, message: impl Into<String>) {
-    let _ = app.emit(
-        "blocking-error",
-        BlockingErrorEvent {
-            message: message.into(),
-        },
-    );
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGINAL: &str = "127.0.0.1 localhost\r\n::1 localhost\r\n";
+
+    fn blocklist(domains: &[&str]) -> ActiveBlocklist {
+        ActiveBlocklist {
+            domains: domains.iter().map(|d| d.to_string()).collect(),
+            subdomains: vec!["www".to_string()],
+            suppress_notifications: false,
+            allowlist: false,
+        }
+    }
+
+    fn blocker_with(contents: &str) -> (FocusBlocker, MemoryHostsStore) {
+        let store = MemoryHostsStore::new(contents);
+        let blocker = FocusBlocker::with_hosts_backend(DirectHosts::new(store.clone(), None));
+        (blocker, store)
+    }
+
+    #[test]
+    fn domains_are_stripped_down_to_the_host() {
+        for input in [
+            "https://www.YouTube.com/feed?x=1",
+            "http://youtube.com:8080/",
+            "youtube.com.",
+            "  youtube.com#top ",
+            "www.youtube.com",
+        ] {
+            assert_eq!(normalize_domain(input).unwrap(), "youtube.com", "{input}");
+        }
+        assert_eq!(normalize_domain("www.com").unwrap(), "www.com");
+        assert_eq!(normalize_domain("m.reddit.com").unwrap(), "m.reddit.com");
+    }
+
+    #[test]
+    fn malformed_hosts_are_rejected() {
+        for input in [
+            "",
+            "localhost",
+            "192.168.1.1",
+            "http://10.0.0.1:80/",
+            "*.example.com",
+            "exa mple.com",
+            "ex_ample.com",
+            "-example.com",
+            "example-.com",
+            "example..com",
+            "127.0.0.1 evil.com",
+        ] {
+            assert!(normalize_domain(input).is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn hostname_length_limits() {
+        let label = "a".repeat(63);
+        assert!(is_valid_hostname(&format!("{label}.com")));
+        assert!(!is_valid_hostname(&format!("a{label}.com")));
+
+        // Four 61-character labels and three dots, plus a five-letter one: 253 characters.
+        let long = [
+            "a".repeat(61),
+            "b".repeat(61),
+            "c".repeat(61),
+            "d".repeat(61),
+        ]
+        .join(".");
+        assert_eq!(long.len(), 247);
+        let at_limit = format!("{long}.abcde");
+        assert_eq!(at_limit.len(), 253);
+        assert!(is_valid_hostname(&at_limit));
+        assert!(!is_valid_hostname(&format!("{long}.abcdef")));
+    }
+
+    #[test]
+    fn expansion_skips_duplicates() {
+        let subdomains = vec!["www".to_string(), "m".to_string(), "www".to_string()];
+        assert_eq!(
+            expand_domain("youtube.com", &subdomains),
+            ["youtube.com", "www.youtube.com", "m.youtube.com"]
+        );
+        assert_eq!(
+            expand_domain("www.com", &["www".to_string()]),
+            ["www.com", "www.www.com"]
+        );
+    }
+
+    #[test]
+    fn status_is_published_on_enable_disable_and_errors() {
+        let (blocker, _store) = blocker_with(ORIGINAL);
+        let seen: Arc<Mutex<Vec<BlockerStatus>>> = Arc::default();
+        let sink = seen.clone();
+        blocker.set_status_listener(Arc::new(move |status| {
+            sink.lock().unwrap().push(status.clone())
+        }));
+
+        blocker
+            .enable(&blocklist(&["x.com", "reddit.com"]))
+            .unwrap();
+        let mut allowlist = blocklist(&["docs.rs"]);
+        allowlist.allowlist = true;
+        assert!(blocker.enable(&allowlist).is_err());
+        blocker.disable().unwrap();
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 3);
+        assert_eq!(
+            seen[0],
+            BlockerStatus {
+                enabled: true,
+                backend: BlockingMethod::Hosts,
+                allowlist: false,
+                domain_count: 2,
+                notifications: NotificationState::Untouched,
+                last_error: None,
+            }
+        );
+        // A failed update keeps the block that's already in place.
+        assert!(seen[1].enabled);
+        assert!(matches!(
+            &seen[1].last_error,
+            Some(AppError::Validation { field, .. }) if field == "blockMode"
+        ));
+        assert!(!seen[2].enabled);
+        assert_eq!(seen[2].domain_count, 0);
+        assert_eq!(seen[2].last_error, None);
+        assert_eq!(blocker.status().unwrap(), seen[2]);
+    }
+
+    #[test]
+    fn dns_sinkhole_keeps_forwarding_outside_work_sessions() {
+        let upstream = dns::tests::fake_upstream();
+        let (blocker, _store) = blocker_with(ORIGINAL);
+        blocker
+            .set_block_method(BlockMethod::Dns {
+                listen: "127.0.0.1:0".parse().unwrap(),
+                upstream,
+            })
+            .unwrap();
+        let server = blocker
+            .inner
+            .lock()
+            .unwrap()
+            .dns
+            .as_ref()
+            .unwrap()
+            .local_addr();
+
+        assert_eq!(
+            dns::tests::resolve(server, "reddit.com"),
+            dns::tests::UPSTREAM_A
+        );
+        blocker.enable(&blocklist(&["reddit.com"])).unwrap();
+        assert_eq!(dns::tests::resolve(server, "reddit.com"), [0; 4]);
+        blocker.disable().unwrap();
+        assert_eq!(
+            dns::tests::resolve(server, "reddit.com"),
+            dns::tests::UPSTREAM_A
+        );
+
+        blocker.set_block_method(BlockMethod::Hosts).unwrap();
+        assert!(blocker.inner.lock().unwrap().dns.is_none());
+    }
+
+    #[test]
+    fn proxy_passes_everything_through_outside_work_sessions() {
+        let origin = proxy::tests::echo_server();
+        let (blocker, _store) = blocker_with(ORIGINAL);
+        blocker
+            .set_block_method(BlockMethod::Proxy {
+                listen: "127.0.0.1:0".parse().unwrap(),
+            })
+            .unwrap();
+        let proxy = blocker
+            .inner
+            .lock()
+            .unwrap()
+            .proxy
+            .as_ref()
+            .unwrap()
+            .local_addr();
+        let connect = format!("CONNECT {origin} HTTP/1.1\r\n\r\n");
+
+        assert!(proxy::tests::send_to(proxy, &connect).starts_with("HTTP/1.1 200"));
+        blocker.enable(&blocklist(&["127.0.0.1"])).unwrap();
+        assert!(proxy::tests::send_to(proxy, &connect).starts_with("HTTP/1.1 403"));
+        blocker.disable().unwrap();
+        assert!(proxy::tests::send_to(proxy, &connect).starts_with("HTTP/1.1 200"));
+
+        blocker.set_block_method(BlockMethod::Hosts).unwrap();
+        assert!(blocker.inner.lock().unwrap().proxy.is_none());
+    }
+
+    #[test]
+    fn blocked_host_matches_domain_and_subdomains_only() {
+        let rules = blocklist(&["youtube.com"]).rules();
+        assert!(rules.is_blocked("youtube.com"));
+        assert!(rules.is_blocked("Music.YouTube.com."));
+        assert!(!rules.is_blocked("notyoutube.com"));
+        assert!(!rules.is_blocked("youtube.com.evil"));
+    }
+
+    #[test]
+    fn allowlist_blocks_everything_but_the_listed_sites() {
+        let rules = SiteRules {
+            allowlist: true,
+            ..blocklist(&["docs.rs", "github.com"]).rules()
+        };
+        assert!(!rules.is_blocked("docs.rs"));
+        assert!(!rules.is_blocked("api.github.com"));
+        assert!(!rules.is_blocked("localhost"));
+        assert!(rules.is_blocked("reddit.com"));
+        assert!(rules.is_blocked("github.com.evil"));
+    }
+
+    #[test]
+    fn allowlist_is_refused_with_the_hosts_method() {
+        let (blocker, store) = blocker_with(ORIGINAL);
+        let allowlist = ActiveBlocklist {
+            allowlist: true,
+            ..blocklist(&["docs.rs"])
+        };
+        assert!(blocker.enable(&allowlist).is_err());
+        assert!(!blocker.is_enabled());
+        assert_eq!(store.contents(), ORIGINAL);
+    }
+
+    #[test]
+    fn enable_and_disable_preserve_crlf() {
+        let (blocker, store) = blocker_with(ORIGINAL);
+
+        blocker.enable(&blocklist(&["youtube.com"])).unwrap();
+        let blocked = store.contents();
+        assert!(blocked.contains("0.0.0.0 www.youtube.com\r\n"));
+        assert!(blocked.contains(":: youtube.com\r\n"));
+        assert!(!blocked.replace("\r\n", "").contains('\n'));
+
+        blocker.disable().unwrap();
+        assert_eq!(store.contents(), ORIGINAL);
+    }
+
+    #[test]
+    fn repeated_enable_and_disable_are_idempotent() {
+        let (blocker, store) = blocker_with(ORIGINAL);
+
+        for _ in 0..3 {
+            blocker.enable(&blocklist(&["reddit.com"])).unwrap();
+            blocker
+                .enable(&blocklist(&["reddit.com", "x.com"]))
+                .unwrap();
+            assert_eq!(store.contents().matches(hosts::HOSTS_START).count(), 1);
+            assert!(store.contents().contains("0.0.0.0 x.com"));
+
+            blocker.disable().unwrap();
+            blocker.disable().unwrap();
+            assert_eq!(store.contents(), ORIGINAL);
+        }
+    }
+
+    #[test]
+    fn disable_strips_leftover_block_from_previous_run() {
+        let leftover = format!(
+            "{ORIGINAL}# focus-timer-block-start\r\n0.0.0.0 x.com\r\n# focus-timer-block-end\r\n"
+        );
+        let (blocker, store) = blocker_with(&leftover);
+
+        blocker.ensure_disabled();
+        assert!(blocker.hosts_recovery().is_none());
+        assert_eq!(store.contents(), ORIGINAL);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn failed_dnd_restore_still_lifts_the_block() {
+        let (blocker, store) = blocker_with(ORIGINAL);
+        blocker.enable(&blocklist(&["x.com"])).unwrap();
+        {
+            let mut inner = blocker.inner.lock().unwrap();
+            inner.dnd = Some(dnd::tests::orphaned_inhibit());
+            inner.toast_suppressed = true;
+        }
+
+        let err = blocker.disable().unwrap_err();
+        assert!(err.message().contains("Do Not Disturb"));
+        assert_eq!(store.contents(), ORIGINAL);
+        let status = blocker.status().unwrap();
+        assert!(!status.enabled);
+        assert!(status.last_error.is_some());
+        assert!(blocker.inner.lock().unwrap().dnd.is_none());
+    }
+
+    #[test]
+    fn restart_after_crash_offers_the_journaled_backup() {
+        let dir = std::env::temp_dir().join(format!("focus-crash-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let journal_path = dir.join("journal.json");
+        let store = MemoryHostsStore::new(ORIGINAL);
+
+        let blocker = FocusBlocker::with_hosts_backend(DirectHosts::new(
+            store.clone(),
+            Some(dir.join("backups")),
+        ));
+        blocker.set_journal(Journal::open(&journal_path));
+        blocker.enable(&blocklist(&["x.com"])).unwrap();
+        let backup = Journal::open(&journal_path).entry().hosts_backup.unwrap();
+
+        // The app dies mid-session and the block gets mangled.
+        drop(blocker);
+        let damaged = store.contents().replace("# focus-timer-block-end", "");
+        hosts::HostsStore::write(&mut store.clone(), &damaged).unwrap();
+
+        let restarted = FocusBlocker::with_hosts_backend(DirectHosts::new(
+            store.clone(),
+            Some(dir.join("backups")),
+        ));
+        restarted.set_journal(Journal::open(&journal_path));
+        restarted.ensure_disabled();
+        assert_eq!(
+            restarted.hosts_recovery().unwrap().backup_path.as_deref(),
+            Some(backup.as_str())
+        );
+
+        restarted.restore_hosts_backup().unwrap();
+        assert_eq!(store.contents(), ORIGINAL);
+        assert!(!journal_path.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn block_damaged_after_startup_is_reported_on_disable() {
+        let (blocker, store) = blocker_with(ORIGINAL);
+        blocker.ensure_disabled();
+        let damaged = format!("{ORIGINAL}# focus-timer-block-start\r\n0.0.0.0 x.com\r\n");
+        hosts::HostsStore::write(&mut store.clone(), &damaged).unwrap();
+
+        assert!(matches!(
+            blocker.disable(),
+            Err(AppError::HostsRecoveryNeeded { .. })
+        ));
+        assert!(matches!(
+            blocker.status().unwrap().last_error,
+            Some(AppError::HostsRecoveryNeeded { .. })
+        ));
+        assert!(blocker.hosts_recovery().is_some());
+        assert_eq!(store.contents(), damaged);
+    }
+
+    #[test]
+    fn damaged_block_is_reported_and_left_untouched() {
+        let damaged = format!("{ORIGINAL}# focus-timer-block-start\r\n0.0.0.0 x.com\r\n");
+        let (blocker, store) = blocker_with(&damaged);
+
+        blocker.ensure_disabled();
+        assert!(blocker.hosts_recovery().is_some());
+        assert!(blocker.enable(&blocklist(&["x.com"])).is_err());
+        blocker.disable().unwrap();
+        assert_eq!(store.contents(), damaged);
+    }
 }
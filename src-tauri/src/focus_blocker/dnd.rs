@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::process::Command;
+
+use zbus::blocking::{Connection, Proxy};
+use zbus::zvariant::Value;
+
+use crate::error::AppError;
+
+const NOTIFICATIONS_NAME: &str = "org.freedesktop.Notifications";
+const NOTIFICATIONS_PATH: &str = "/org/freedesktop/Notifications";
+const GNOME_SCHEMA: &str = "org.gnome.desktop.notifications";
+const GNOME_KEY: &str = "show-banners";
+
+/// How Linux notifications were silenced, so exactly that can be undone.
+pub enum Suppression {
+    /// `Inhibit` on the notification server (KDE Plasma and others). The server drops the
+    /// inhibition when this connection closes, so it must be kept alive until restored.
+    Inhibited { conn: Connection, cookie: u32 },
+    /// GNOME has no `Inhibit`; its Do Not Disturb switch is the `show-banners` setting.
+    GnomeBanners { was_shown: bool },
+}
+
+/// Turns on Do Not Disturb through the session bus, falling back to GNOME's setting.
+pub fn suppress() -> Result<Suppression, AppError> {
+    let inhibit_error = match Connection::session() {
+        Ok(conn) => match inhibit(conn) {
+            Ok(suppression) => return Ok(suppression),
+            Err(e) => e,
+        },
+        Err(e) => AppError::Unsupported {
+            message: format!("no session bus: {e}"),
+        },
+    };
+    suppress_gnome_banners().map_err(|gnome_error| AppError::Unsupported {
+        message: format!("couldn't enable Do Not Disturb ({inhibit_error}; {gnome_error})"),
+    })
+}
+
+pub fn restore(suppression: &Suppression) -> Result<(), AppError> {
+    match suppression {
+        Suppression::Inhibited { conn, cookie } => uninhibit(conn, *cookie),
+        Suppression::GnomeBanners { was_shown } => set_gnome_banners(*was_shown),
+    }
+}
+
+fn notifications_proxy(conn: &Connection) -> Result<Proxy<'static>, AppError> {
+    Proxy::new(
+        conn,
+        NOTIFICATIONS_NAME,
+        NOTIFICATIONS_PATH,
+        NOTIFICATIONS_NAME,
+    )
+    .map_err(|e| AppError::Io {
+        message: format!("failed to reach the notification server: {e}"),
+    })
+}
+
+pub fn inhibit(conn: Connection) -> Result<Suppression, AppError> {
+    let hints: HashMap<&str, Value<'_>> = HashMap::new();
+    let cookie: u32 = notifications_proxy(&conn)?
+        .call("Inhibit", &("focus-timer", "Focus session", hints))
+        .map_err(|e| AppError::Io {
+            message: format!("the notification server refused to inhibit: {e}"),
+        })?;
+    Ok(Suppression::Inhibited { conn, cookie })
+}
+
+fn uninhibit(conn: &Connection, cookie: u32) -> Result<(), AppError> {
+    notifications_proxy(conn)?
+        .call::<_, _, ()>("UnInhibit", &(cookie,))
+        .map_err(|e| AppError::Io {
+            message: format!("failed to lift the notification inhibit: {e}"),
+        })
+}
+
+fn gsettings(args: &[&str]) -> Result<String, AppError> {
+    let output = Command::new("gsettings")
+        .args(args)
+        .output()
+        .map_err(|e| AppError::io("gsettings unavailable", e))?;
+    if !output.status.success() {
+        return Err(AppError::Io {
+            message: format!(
+                "gsettings failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn suppress_gnome_banners() -> Result<Suppression, AppError> {
+    let was_shown = match gsettings(&["get", GNOME_SCHEMA, GNOME_KEY])?.as_str() {
+        "true" => true,
+        "false" => false,
+        other => {
+            return Err(AppError::Unsupported {
+                message: format!("unexpected {GNOME_KEY} value {other:?}"),
+            })
+        }
+    };
+    set_gnome_banners(false)?;
+    Ok(Suppression::GnomeBanners { was_shown })
+}
+
+fn set_gnome_banners(shown: bool) -> Result<(), AppError> {
+    gsettings(&[
+        "set",
+        GNOME_SCHEMA,
+        GNOME_KEY,
+        if shown { "true" } else { "false" },
+    ])
+    .map(|_| ())
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use std::os::unix::net::UnixStream;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    use zbus::blocking::connection::Builder;
+    use zbus::zvariant::OwnedValue;
+    use zbus::Guid;
+
+    /// Stand-in notification server that only implements the inhibit API.
+    #[derive(Clone, Default)]
+    struct FakeNotifications {
+        active: Arc<Mutex<Vec<u32>>>,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.Notifications")]
+    impl FakeNotifications {
+        fn inhibit(
+            &self,
+            desktop_entry: String,
+            _reason: String,
+            _hints: HashMap<String, OwnedValue>,
+        ) -> u32 {
+            assert_eq!(desktop_entry, "focus-timer");
+            let mut active = self.active.lock().unwrap();
+            let cookie = 41 + active.len() as u32;
+            active.push(cookie);
+            cookie
+        }
+
+        fn un_inhibit(&self, cookie: u32) {
+            self.active.lock().unwrap().retain(|c| *c != cookie);
+        }
+    }
+
+    /// Client connection to a fake server over a private socket pair.
+    // Newer 5.x releases deprecate `unix_stream` for a variant older ones don't have.
+    #[allow(deprecated)]
+    fn connect(service: FakeNotifications) -> (Connection, Connection) {
+        let (server_side, client_side) = UnixStream::pair().unwrap();
+        let server = thread::spawn(move || {
+            Builder::unix_stream(server_side)
+                .server(Guid::generate())
+                .unwrap()
+                .p2p()
+                .serve_at(NOTIFICATIONS_PATH, service)
+                .unwrap()
+                .build()
+                .unwrap()
+        });
+        let client = Builder::unix_stream(client_side).p2p().build().unwrap();
+        (server.join().unwrap(), client)
+    }
+
+    /// An inhibit whose notification server has since gone away, so restoring it fails.
+    pub(in crate::focus_blocker) fn orphaned_inhibit() -> Suppression {
+        let (server, client) = connect(FakeNotifications::default());
+        let suppression = inhibit(client).unwrap();
+        drop(server);
+        suppression
+    }
+
+    #[test]
+    fn inhibit_and_restore_through_stand_in_service() {
+        let service = FakeNotifications::default();
+        let (_server, client) = connect(service.clone());
+
+        let suppression = inhibit(client).unwrap();
+        assert!(matches!(
+            suppression,
+            Suppression::Inhibited { cookie: 41, .. }
+        ));
+        assert_eq!(*service.active.lock().unwrap(), vec![41]);
+
+        restore(&suppression).unwrap();
+        assert!(service.active.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,357 @@
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use super::SiteRules;
+use crate::error::AppError;
+
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+const CLASS_IN: u16 = 1;
+const RCODE_NXDOMAIN: u8 = 3;
+const SINKHOLE_TTL: u32 = 60;
+const MAX_PACKET: usize = 4096;
+
+/// Tiny DNS resolver for focus mode: answers `0.0.0.0`/`::` (or NXDOMAIN for other record
+/// types) for blocked names and relays everything else to an upstream resolver.
+///
+/// Unlike the hosts file it matches every subdomain, and it doesn't need admin rights when
+/// listening on an unprivileged port. The system (or browser) must be pointed at it.
+pub struct DnsSinkhole {
+    #[cfg(test)]
+    local_addr: SocketAddr,
+    rules: Arc<RwLock<SiteRules>>,
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl DnsSinkhole {
+    pub fn start(
+        listen: SocketAddr,
+        upstream: SocketAddr,
+        rules: SiteRules,
+    ) -> Result<Self, AppError> {
+        let socket = UdpSocket::bind(listen)
+            .map_err(|e| AppError::io(format!("failed to listen for DNS on {listen}"), e))?;
+        #[cfg(test)]
+        let local_addr = socket
+            .local_addr()
+            .map_err(|e| AppError::io("failed to read DNS listen address", e))?;
+        // Wake up regularly so `stop` is noticed without needing a packet.
+        socket
+            .set_read_timeout(Some(Duration::from_millis(200)))
+            .map_err(|e| AppError::io("failed to configure DNS socket", e))?;
+
+        let rules = Arc::new(RwLock::new(rules));
+        let stop = Arc::new(AtomicBool::new(false));
+        let worker = {
+            let rules = rules.clone();
+            let stop = stop.clone();
+            thread::spawn(move || serve(socket, upstream, rules, stop))
+        };
+
+        Ok(Self {
+            #[cfg(test)]
+            local_addr,
+            rules,
+            stop,
+            worker: Some(worker),
+        })
+    }
+
+    #[cfg(test)]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    pub fn set_rules(&self, rules: SiteRules) {
+        if let Ok(mut current) = self.rules.write() {
+            *current = rules;
+        }
+    }
+
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for DnsSinkhole {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+fn serve(
+    socket: UdpSocket,
+    upstream: SocketAddr,
+    rules: Arc<RwLock<SiteRules>>,
+    stop: Arc<AtomicBool>,
+) {
+    let mut buf = [0u8; MAX_PACKET];
+    while !stop.load(Ordering::SeqCst) {
+        let (len, client) = match socket.recv_from(&mut buf) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        let packet = &buf[..len];
+        let Some(question) = parse_question(packet) else {
+            continue;
+        };
+
+        let is_blocked = rules
+            .read()
+            .map(|rules| rules.is_blocked(&question.name))
+            .unwrap_or(false);
+        if is_blocked {
+            let _ = socket.send_to(&sinkhole_response(packet, &question), client);
+            continue;
+        }
+
+        // Forward on a separate thread so one slow upstream answer doesn't stall other lookups.
+        let Ok(reply_socket) = socket.try_clone() else {
+            continue;
+        };
+        let query = packet.to_vec();
+        thread::spawn(move || {
+            if let Some(answer) = forward(&query, upstream) {
+                let _ = reply_socket.send_to(&answer, client);
+            }
+        });
+    }
+}
+
+fn forward(query: &[u8], upstream: SocketAddr) -> Option<Vec<u8>> {
+    let bind: SocketAddr = if upstream.is_ipv4() {
+        "0.0.0.0:0".parse().ok()?
+    } else {
+        "[::]:0".parse().ok()?
+    };
+    let socket = UdpSocket::bind(bind).ok()?;
+    socket.set_read_timeout(Some(Duration::from_secs(3))).ok()?;
+    socket.connect(upstream).ok()?;
+    socket.send(query).ok()?;
+
+    let mut buf = [0u8; MAX_PACKET];
+    let len = socket.recv(&mut buf).ok()?;
+    Some(buf[..len].to_vec())
+}
+
+#[derive(Debug, PartialEq)]
+struct Question {
+    name: String,
+    qtype: u16,
+    qclass: u16,
+    /// Offset just past the first question, i.e. where answers start.
+    end: usize,
+}
+
+fn parse_question(packet: &[u8]) -> Option<Question> {
+    if packet.len() < 12 {
+        return None;
+    }
+    let is_response = packet[2] & 0x80 != 0;
+    let qdcount = u16::from_be_bytes([packet[4], packet[5]]);
+    if is_response || qdcount == 0 {
+        return None;
+    }
+
+    let mut pos = 12;
+    let mut labels: Vec<String> = Vec::new();
+    loop {
+        let len = *packet.get(pos)? as usize;
+        pos += 1;
+        if len == 0 {
+            break;
+        }
+        // Compression pointers aren't valid in a query's first question.
+        if len > 63 {
+            return None;
+        }
+        let label = packet.get(pos..pos + len)?;
+        labels.push(String::from_utf8_lossy(label).to_ascii_lowercase());
+        pos += len;
+    }
+    let qtype = u16::from_be_bytes([*packet.get(pos)?, *packet.get(pos + 1)?]);
+    let qclass = u16::from_be_bytes([*packet.get(pos + 2)?, *packet.get(pos + 3)?]);
+
+    Some(Question {
+        name: labels.join("."),
+        qtype,
+        qclass,
+        end: pos + 4,
+    })
+}
+
+fn sinkhole_response(query: &[u8], question: &Question) -> Vec<u8> {
+    let rdata: Option<&[u8]> = match (question.qtype, question.qclass) {
+        (TYPE_A, CLASS_IN) => Some(&[0; 4]),
+        (TYPE_AAAA, CLASS_IN) => Some(&[0; 16]),
+        _ => None,
+    };
+
+    let mut out = Vec::with_capacity(question.end + 32);
+    out.extend_from_slice(&query[..2]); // id
+
+    // QR=1, keep opcode and RD; RA=1; NOERROR for sinkholed addresses, NXDOMAIN otherwise.
+    out.push(0x80 | (query[2] & 0x79));
+    out.push(0x80 | if rdata.is_some() { 0 } else { RCODE_NXDOMAIN });
+    out.extend_from_slice(&1u16.to_be_bytes()); // qdcount
+    out.extend_from_slice(&(rdata.is_some() as u16).to_be_bytes()); // ancount
+    out.extend_from_slice(&0u16.to_be_bytes()); // nscount
+    out.extend_from_slice(&0u16.to_be_bytes()); // arcount
+    out.extend_from_slice(&query[12..question.end]);
+
+    if let Some(rdata) = rdata {
+        out.extend_from_slice(&0xC00Cu16.to_be_bytes()); // pointer to the question name
+        out.extend_from_slice(&question.qtype.to_be_bytes());
+        out.extend_from_slice(&CLASS_IN.to_be_bytes());
+        out.extend_from_slice(&SINKHOLE_TTL.to_be_bytes());
+        out.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        out.extend_from_slice(rdata);
+    }
+    out
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    /// What [`fake_upstream`] answers for every A query.
+    pub(in crate::focus_blocker) const UPSTREAM_A: [u8; 4] = [10, 1, 2, 3];
+
+    fn query(id: u16, name: &str, qtype: u16) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&id.to_be_bytes());
+        out.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+        for label in name.split('.') {
+            out.push(label.len() as u8);
+            out.extend_from_slice(label.as_bytes());
+        }
+        out.push(0);
+        out.extend_from_slice(&qtype.to_be_bytes());
+        out.extend_from_slice(&CLASS_IN.to_be_bytes());
+        out
+    }
+
+    /// Stand-in upstream resolver that answers every query with a fixed A record.
+    pub(in crate::focus_blocker) fn fake_upstream() -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = socket.local_addr().unwrap();
+        thread::spawn(move || {
+            let mut buf = [0u8; MAX_PACKET];
+            while let Ok((len, from)) = socket.recv_from(&mut buf) {
+                let question = parse_question(&buf[..len]).unwrap();
+                let mut answer = sinkhole_response(&buf[..len], &question);
+                let n = answer.len();
+                answer[n - 4..].copy_from_slice(&UPSTREAM_A);
+                let _ = socket.send_to(&answer, from);
+            }
+        });
+        addr
+    }
+
+    fn rules(domains: &[&str], allowlist: bool) -> SiteRules {
+        SiteRules {
+            domains: domains.iter().map(|d| d.to_string()).collect(),
+            allowlist,
+        }
+    }
+
+    fn ask(server: SocketAddr, packet: &[u8]) -> Vec<u8> {
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        client.send_to(packet, server).unwrap();
+        let mut buf = [0u8; MAX_PACKET];
+        let len = client.recv(&mut buf).unwrap();
+        buf[..len].to_vec()
+    }
+
+    /// The address `server` gives for `name`.
+    pub(in crate::focus_blocker) fn resolve(server: SocketAddr, name: &str) -> [u8; 4] {
+        let answer = ask(server, &query(1, name, TYPE_A));
+        answer[answer.len() - 4..].try_into().unwrap()
+    }
+
+    fn rcode(answer: &[u8]) -> u8 {
+        answer[3] & 0x0F
+    }
+
+    fn ancount(answer: &[u8]) -> u16 {
+        u16::from_be_bytes([answer[6], answer[7]])
+    }
+
+    #[test]
+    fn parses_question() {
+        let packet = query(7, "WWW.Example.com", TYPE_AAAA);
+        let q = parse_question(&packet).unwrap();
+        assert_eq!(q.name, "www.example.com");
+        assert_eq!(q.qtype, TYPE_AAAA);
+        assert_eq!(q.end, packet.len());
+        assert!(parse_question(&packet[..10]).is_none());
+    }
+
+    #[test]
+    fn sinkholes_blocked_names_and_forwards_the_rest() {
+        let upstream = fake_upstream();
+        let sinkhole = DnsSinkhole::start(
+            "127.0.0.1:0".parse().unwrap(),
+            upstream,
+            rules(&["reddit.com"], false),
+        )
+        .unwrap();
+        let server = sinkhole.local_addr();
+
+        let a = ask(server, &query(1, "new.reddit.com", TYPE_A));
+        assert_eq!(&a[..2], &1u16.to_be_bytes());
+        assert_eq!((rcode(&a), ancount(&a)), (0, 1));
+        assert_eq!(&a[a.len() - 4..], &[0, 0, 0, 0]);
+
+        let aaaa = ask(server, &query(2, "reddit.com", TYPE_AAAA));
+        assert_eq!(ancount(&aaaa), 1);
+        assert_eq!(&aaaa[aaaa.len() - 16..], &[0; 16]);
+
+        let mx = ask(server, &query(3, "reddit.com", 15));
+        assert_eq!((rcode(&mx), ancount(&mx)), (RCODE_NXDOMAIN, 0));
+
+        let allowed = ask(server, &query(4, "docs.rs", TYPE_A));
+        assert_eq!(&allowed[..2], &4u16.to_be_bytes());
+        assert_eq!(&allowed[allowed.len() - 4..], &UPSTREAM_A);
+
+        sinkhole.set_rules(rules(&["docs.rs"], false));
+        let now_blocked = ask(server, &query(5, "docs.rs", TYPE_A));
+        assert_eq!(&now_blocked[now_blocked.len() - 4..], &[0, 0, 0, 0]);
+
+        sinkhole.stop();
+    }
+
+    #[test]
+    fn allowlist_sinkholes_everything_else() {
+        let upstream = fake_upstream();
+        let sinkhole = DnsSinkhole::start(
+            "127.0.0.1:0".parse().unwrap(),
+            upstream,
+            rules(&["docs.rs", "github.com"], true),
+        )
+        .unwrap();
+        let server = sinkhole.local_addr();
+
+        let allowed = ask(server, &query(1, "api.github.com", TYPE_A));
+        assert_eq!(&allowed[allowed.len() - 4..], &UPSTREAM_A);
+
+        let blocked = ask(server, &query(2, "reddit.com", TYPE_A));
+        assert_eq!(&blocked[blocked.len() - 4..], &[0, 0, 0, 0]);
+
+        sinkhole.stop();
+    }
+}
@@ -0,0 +1,746 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+#[cfg(test)]
+use std::sync::{Arc, Mutex};
+
+use chrono::Local;
+use serde::Serialize;
+
+use super::is_valid_hostname;
+use crate::error::AppError;
+
+/// A hosts file whose focus-timer section looks damaged (e.g. the app died mid-write).
+/// It is left untouched until the user chooses to restore the pre-focus backup.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostsRecovery {
+    pub hosts_path: String,
+    pub reason: String,
+    pub backup_path: Option<String>,
+}
+
+/// Raw access to a hosts file's contents, so the block logic can run against a real file,
+/// a file at an arbitrary path, or memory (tests).
+pub trait HostsStore: Send {
+    fn read(&self) -> Result<String, AppError>;
+    fn write(&mut self, contents: &str) -> Result<(), AppError>;
+    /// Human-readable location for error messages and the recovery prompt.
+    fn location(&self) -> String;
+}
+
+/// A hosts file on disk, written atomically.
+#[derive(Debug, Clone)]
+pub struct FileHostsStore {
+    path: PathBuf,
+}
+
+impl FileHostsStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// The system hosts file, unless overridden by settings or [`HOSTS_PATH_ENV`].
+    pub fn system(override_path: Option<&Path>) -> Self {
+        Self::new(hosts_path(override_path))
+    }
+}
+
+impl HostsStore for FileHostsStore {
+    fn read(&self) -> Result<String, AppError> {
+        read_to_string(&self.path)
+    }
+
+    fn write(&mut self, contents: &str) -> Result<(), AppError> {
+        write_string(&self.path, contents)
+    }
+
+    fn location(&self) -> String {
+        self.path.display().to_string()
+    }
+}
+
+/// In-memory hosts file. Clones share the same contents, so a test can keep one handle
+/// and hand the other to the blocker.
+#[cfg(test)]
+#[derive(Debug, Clone, Default)]
+pub struct MemoryHostsStore {
+    contents: Arc<Mutex<String>>,
+}
+
+#[cfg(test)]
+impl MemoryHostsStore {
+    pub fn new(contents: impl Into<String>) -> Self {
+        Self {
+            contents: Arc::new(Mutex::new(contents.into())),
+        }
+    }
+
+    pub fn contents(&self) -> String {
+        self.contents.lock().map(|c| c.clone()).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+impl HostsStore for MemoryHostsStore {
+    fn read(&self) -> Result<String, AppError> {
+        self.contents
+            .lock()
+            .map(|c| c.clone())
+            .map_err(|_| AppError::poisoned("hosts store"))
+    }
+
+    fn write(&mut self, contents: &str) -> Result<(), AppError> {
+        let mut current = self
+            .contents
+            .lock()
+            .map_err(|_| AppError::poisoned("hosts store"))?;
+        *current = contents.to_string();
+        Ok(())
+    }
+
+    fn location(&self) -> String {
+        "<memory>".to_string()
+    }
+}
+
+/// Applies or clears the focus-timer section of a hosts file.
+///
+/// The app either edits the file itself ([`DirectHosts`]) or asks the elevated
+/// `focus-hosts-helper` process to do it, so only the helper needs admin/root.
+pub trait HostsBackend: Send {
+    fn apply(&mut self, hosts: &[String]) -> Result<(), AppError>;
+    fn clear(&mut self) -> Result<(), AppError>;
+
+    /// Startup check: clears a leftover block, or reports a damaged one instead of guessing.
+    fn recover(&mut self) -> Result<Option<HostsRecovery>, AppError> {
+        self.clear().map(|_| None)
+    }
+
+    /// Overwrites the hosts file with a pre-focus backup: `backup` if given, otherwise the
+    /// most recent one.
+    fn restore_backup(&mut self, _backup: Option<&Path>) -> Result<(), AppError> {
+        Err(AppError::Unsupported {
+            message: "this hosts backend doesn't keep backups".to_string(),
+        })
+    }
+
+    /// The backup taken before the current block was written, if any.
+    fn last_backup(&self) -> Option<PathBuf> {
+        None
+    }
+}
+
+impl Default for Box<dyn HostsBackend> {
+    fn default() -> Self {
+        Box::new(DirectHosts::system(None, None))
+    }
+}
+
+/// Edits a [`HostsStore`] in-process; for the system file the app needs write access.
+pub struct DirectHosts {
+    store: Box<dyn HostsStore>,
+    /// Whether this session already wrote a block (and therefore took a backup).
+    applied: bool,
+    /// Where pre-focus copies of the hosts file are kept; `None` disables backups.
+    backup_dir: Option<PathBuf>,
+    last_backup: Option<PathBuf>,
+}
+
+const MAX_HOSTS_BACKUPS: usize = 10;
+
+impl DirectHosts {
+    pub fn new(store: impl HostsStore + 'static, backup_dir: Option<PathBuf>) -> Self {
+        Self {
+            store: Box::new(store),
+            applied: false,
+            backup_dir,
+            last_backup: None,
+        }
+    }
+
+    pub fn system(override_path: Option<&Path>, backup_dir: Option<PathBuf>) -> Self {
+        Self::new(FileHostsStore::system(override_path), backup_dir)
+    }
+
+    pub fn location(&self) -> String {
+        self.store.location()
+    }
+
+    fn backup(&mut self, contents: &str) -> Result<(), AppError> {
+        let Some(dir) = &self.backup_dir else {
+            return Ok(());
+        };
+        fs::create_dir_all(dir)
+            .map_err(|e| AppError::io(format!("failed to create {dir:?}"), e))?;
+
+        let path = dir.join(format!(
+            "hosts-{}.bak",
+            Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        fs::write(&path, contents)
+            .map_err(|e| AppError::io(format!("failed to write {path:?}"), e))?;
+        self.last_backup = Some(path);
+
+        let mut backups = self.backups();
+        while backups.len() > MAX_HOSTS_BACKUPS {
+            let _ = fs::remove_file(backups.remove(0));
+        }
+        Ok(())
+    }
+
+    /// Backups sorted oldest first (the timestamped names sort chronologically).
+    fn backups(&self) -> Vec<PathBuf> {
+        let Some(dir) = &self.backup_dir else {
+            return Vec::new();
+        };
+        let mut out: Vec<PathBuf> = fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok().map(|e| e.path()))
+                    .filter(|p| {
+                        p.file_name()
+                            .and_then(|n| n.to_str())
+                            .is_some_and(|n| n.starts_with("hosts-") && n.ends_with(".bak"))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        out.sort();
+        out
+    }
+}
+
+impl HostsBackend for DirectHosts {
+    fn apply(&mut self, hosts: &[String]) -> Result<(), AppError> {
+        let existing = self.store.read()?;
+        if !self.applied {
+            // First write of this session: keep a copy of what the user had before focusing.
+            self.backup(&strip_hosts_block(&existing)?)?;
+        }
+        self.store.write(&append_hosts_block(&existing, hosts)?)?;
+        self.applied = true;
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<(), AppError> {
+        let existing = self.store.read()?;
+        let stripped = strip_hosts_block(&existing)?;
+        if stripped != existing {
+            self.store.write(&stripped)?;
+        }
+        self.applied = false;
+        Ok(())
+    }
+
+    fn recover(&mut self) -> Result<Option<HostsRecovery>, AppError> {
+        let existing = self.store.read()?;
+        if let Some(reason) = find_block_damage(&existing) {
+            return Ok(Some(HostsRecovery {
+                hosts_path: self.store.location(),
+                reason,
+                backup_path: self.backups().pop().map(|p| p.display().to_string()),
+            }));
+        }
+        self.clear().map(|_| None)
+    }
+
+    fn restore_backup(&mut self, backup: Option<&Path>) -> Result<(), AppError> {
+        let backup = match backup {
+            Some(path) => path.to_path_buf(),
+            None => self
+                .backups()
+                .pop()
+                .ok_or_else(|| AppError::not_found("no hosts file backup is available"))?,
+        };
+        let contents = read_to_string(&backup)?;
+        self.store.write(&contents)?;
+        self.applied = false;
+        Ok(())
+    }
+
+    fn last_backup(&self) -> Option<PathBuf> {
+        self.last_backup.clone()
+    }
+}
+
+pub(crate) const HOSTS_START: &str = "# focus-timer-block-start";
+/// Start marker used when the file didn't end with a newline and we had to add one; the
+/// strip removes that newline again so the original bytes come back exactly.
+const HOSTS_START_AFTER_NEWLINE: &str = "# focus-timer-block-start (added newline)";
+const HOSTS_END: &str = "# focus-timer-block-end";
+
+fn is_start_marker(line: &str) -> bool {
+    line == HOSTS_START || line == HOSTS_START_AFTER_NEWLINE
+}
+
+/// Environment variable that points the blocker at a different hosts file.
+pub const HOSTS_PATH_ENV: &str = "FOCUS_TIMER_HOSTS_FILE";
+
+#[cfg(target_os = "windows")]
+fn default_hosts_path() -> PathBuf {
+    let system_root = std::env::var("SystemRoot").unwrap_or_else(|_| "C:\\Windows".to_string());
+    PathBuf::from(system_root)
+        .join("System32")
+        .join("drivers")
+        .join("etc")
+        .join("hosts")
+}
+
+#[cfg(not(target_os = "windows"))]
+fn default_hosts_path() -> PathBuf {
+    PathBuf::from("/etc/hosts")
+}
+
+pub fn hosts_path(override_path: Option<&Path>) -> PathBuf {
+    if let Some(path) = override_path {
+        return path.to_path_buf();
+    }
+    match std::env::var_os(HOSTS_PATH_ENV) {
+        Some(path) if !path.is_empty() => PathBuf::from(path),
+        _ => default_hosts_path(),
+    }
+}
+
+/// Hint appended to errors when the hosts file can't be written.
+pub fn elevation_hint() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "try running the app as Administrator"
+    } else {
+        "the hosts file must be writable by this user; run with sudo or grant write access"
+    }
+}
+
+fn detect_newline(text: &str) -> &'static str {
+    if text.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    }
+}
+
+/// Removes every focus-timer block, leaving all other bytes exactly as they were.
+///
+/// An unterminated block is an error rather than "strip to the end of the file", since that
+/// would silently delete whatever the user has after the marker.
+pub(crate) fn strip_hosts_block(existing: &str) -> Result<String, AppError> {
+    let mut out = String::with_capacity(existing.len());
+    let mut kept_from = 0;
+    let mut open_at: Option<usize> = None;
+    let mut offset = 0;
+
+    for line in existing.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        let content = line.trim_end();
+
+        match open_at {
+            None if is_start_marker(content) => {
+                let mut from = line_start;
+                if content == HOSTS_START_AFTER_NEWLINE {
+                    let before = &existing[..from];
+                    if before.ends_with("\r\n") {
+                        from -= 2;
+                    } else if before.ends_with('\n') {
+                        from -= 1;
+                    }
+                }
+                open_at = Some(from);
+            }
+            Some(from) if content == HOSTS_END => {
+                out.push_str(&existing[kept_from..from]);
+                kept_from = offset;
+                open_at = None;
+            }
+            _ => {}
+        }
+    }
+
+    if open_at.is_some() {
+        return Err(AppError::HostsRecoveryNeeded {
+            message: format!(
+                "the hosts file has \"{HOSTS_START}\" without \"{HOSTS_END}\"; left it untouched"
+            ),
+        });
+    }
+    out.push_str(&existing[kept_from..]);
+    Ok(out)
+}
+
+/// Describes why the focus-timer section can't be stripped safely, if it can't.
+pub(crate) fn find_block_damage(text: &str) -> Option<String> {
+    let mut inside = false;
+    for line in text.lines() {
+        let line = line.trim_end();
+        if is_start_marker(line) {
+            if inside {
+                return Some(
+                    "the focus-timer block starts twice without an end marker".to_string(),
+                );
+            }
+            inside = true;
+        } else if line == HOSTS_END {
+            if !inside {
+                return Some("the focus-timer block ends without a start marker".to_string());
+            }
+            inside = false;
+        } else if inside && !is_block_entry(line) {
+            return Some(format!(
+                "unexpected line inside the focus-timer block: {line:?}"
+            ));
+        }
+    }
+    if inside {
+        return Some(
+            "the focus-timer block has no end marker (the write was cut short)".to_string(),
+        );
+    }
+    None
+}
+
+/// Lines we write between the markers: `0.0.0.0 host` or `:: host`.
+fn is_block_entry(line: &str) -> bool {
+    line.strip_prefix("0.0.0.0 ")
+        .or_else(|| line.strip_prefix(":: "))
+        .is_some_and(is_valid_hostname)
+}
+
+pub(crate) fn append_hosts_block(existing: &str, hosts: &[String]) -> Result<String, AppError> {
+    let mut out = strip_hosts_block(existing)?;
+    let newline = detect_newline(existing);
+
+    let start_marker = if !out.is_empty() && !out.ends_with('\n') {
+        out.push_str(newline);
+        HOSTS_START_AFTER_NEWLINE
+    } else {
+        HOSTS_START
+    };
+
+    out.push_str(start_marker);
+    out.push_str(newline);
+    for host in hosts {
+        out.push_str("0.0.0.0 ");
+        out.push_str(host);
+        out.push_str(newline);
+        // Without an IPv6 entry, dual-stack resolvers can still reach the site over AAAA.
+        out.push_str(":: ");
+        out.push_str(host);
+        out.push_str(newline);
+    }
+    out.push_str(HOSTS_END);
+    out.push_str(newline);
+    Ok(out)
+}
+
+fn read_to_string(path: &Path) -> Result<String, AppError> {
+    fs::read_to_string(path).map_err(|e| AppError::io(format!("failed to read {path:?}"), e))
+}
+
+/// Writes via a temp file + rename so a crash or power cut never leaves a half-written file.
+///
+/// The file is rewritten in place instead when the rename couldn't keep the original's owner,
+/// ACLs and SELinux label (on Unix, when it belongs to someone else and we aren't root), when the temp file can't be
+/// created (only the hosts file itself was made writable), or when the file can't be replaced
+/// (a bind mount in a container). Callers take a backup before the first write.
+fn write_string(path: &Path, contents: &str) -> Result<(), AppError> {
+    // Follow symlinks (e.g. NixOS's /etc/hosts) so the rename replaces the real file.
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let file_name = target
+        .file_name()
+        .ok_or_else(|| {
+            AppError::validation("hostsPath", format!("invalid hosts file path {path:?}"))
+        })?
+        .to_string_lossy()
+        .into_owned();
+    let tmp = target.with_file_name(format!(".{file_name}.focus-timer.tmp"));
+
+    let result = if can_preserve_metadata(&target) {
+        replace_with_temp_file(&tmp, &target, contents)
+    } else {
+        Err(io::ErrorKind::Unsupported.into())
+    };
+    let result = match result {
+        Err(e)
+            if matches!(
+                e.kind(),
+                io::ErrorKind::PermissionDenied
+                    | io::ErrorKind::ReadOnlyFilesystem
+                    | io::ErrorKind::ResourceBusy
+                    | io::ErrorKind::CrossesDevices
+                    | io::ErrorKind::Unsupported
+            ) =>
+        {
+            write_in_place(&target, contents)
+        }
+        other => other,
+    };
+    result.map_err(|e| AppError::io(format!("failed to write {path:?}"), e))
+}
+
+fn replace_with_temp_file(tmp: &Path, target: &Path, contents: &str) -> io::Result<()> {
+    let result = (|| {
+        let mut file = fs::File::create(tmp)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        // Rather write in place than swap in a file the system treats differently.
+        copy_metadata(target, tmp).map_err(|e| io::Error::new(io::ErrorKind::Unsupported, e))?;
+        fs::rename(tmp, target)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(tmp);
+    }
+    result
+}
+
+fn write_in_place(target: &Path, contents: &str) -> io::Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(target)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()
+}
+
+/// The replacement can only be given the original's owner by root, or when that owner is us
+/// (a user-owned `hostsPath`). A missing file has no owner to keep.
+#[cfg(unix)]
+fn can_preserve_metadata(target: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    // SAFETY: geteuid has no preconditions and can't fail.
+    let euid = unsafe { libc::geteuid() };
+    euid == 0 || fs::metadata(target).map_or(true, |meta| meta.uid() == euid)
+}
+
+/// The hosts file normally inherits its ACL from its directory, as the temp file does.
+#[cfg(not(unix))]
+fn can_preserve_metadata(_target: &Path) -> bool {
+    true
+}
+
+/// Gives `to` the mode (and on Unix the owner and extended attributes, which hold ACLs and
+/// SELinux labels) of `from`, if `from` exists.
+fn copy_metadata(from: &Path, to: &Path) -> io::Result<()> {
+    let meta = match fs::metadata(from) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    fs::set_permissions(to, meta.permissions())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        std::os::unix::fs::chown(to, Some(meta.uid()), Some(meta.gid()))?;
+    }
+    #[cfg(target_os = "linux")]
+    copy_xattrs(from, to)?;
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn copy_xattrs(from: &Path, to: &Path) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let from = CString::new(from.as_os_str().as_bytes())?;
+    let to = CString::new(to.as_os_str().as_bytes())?;
+
+    let mut names = vec![0u8; 4096];
+    // SAFETY: `from` is NUL-terminated and `names` has room for `names.len()` bytes.
+    let len = unsafe { libc::listxattr(from.as_ptr(), names.as_mut_ptr().cast(), names.len()) };
+    if len < 0 {
+        let e = io::Error::last_os_error();
+        // No extended attributes on this filesystem: nothing to carry over.
+        return match e.raw_os_error() {
+            Some(libc::ENOTSUP) => Ok(()),
+            _ => Err(e),
+        };
+    }
+    names.truncate(len as usize);
+
+    for name in names.split(|&b| b == 0).filter(|name| !name.is_empty()) {
+        let name = CString::new(name)?;
+        let mut value = vec![0u8; 64 * 1024];
+        // SAFETY: both names are NUL-terminated and `value` has room for `value.len()` bytes.
+        let len = unsafe {
+            libc::getxattr(
+                from.as_ptr(),
+                name.as_ptr(),
+                value.as_mut_ptr().cast(),
+                value.len(),
+            )
+        };
+        if len < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: both names are NUL-terminated and `len` bytes of `value` were just filled.
+        let set = unsafe {
+            libc::setxattr(
+                to.as_ptr(),
+                name.as_ptr(),
+                value.as_ptr().cast(),
+                len as usize,
+                0,
+            )
+        };
+        if set != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hosts(list: &[&str]) -> Vec<String> {
+        list.iter().map(|h| h.to_string()).collect()
+    }
+
+    #[test]
+    fn strip_removes_leftover_block() {
+        let text = "127.0.0.1 localhost\n# focus-timer-block-start\n0.0.0.0 x.com\n:: x.com\n# focus-timer-block-end\n::1 localhost\n";
+        assert_eq!(
+            strip_hosts_block(text).unwrap(),
+            "127.0.0.1 localhost\n::1 localhost\n"
+        );
+    }
+
+    #[test]
+    fn strip_without_block_is_identity() {
+        let text = "127.0.0.1 localhost\r\n# comment";
+        assert_eq!(strip_hosts_block(text).unwrap(), text);
+    }
+
+    #[test]
+    fn append_preserves_crlf() {
+        let text = "127.0.0.1 localhost\r\n";
+        let out = append_hosts_block(text, &hosts(&["x.com"])).unwrap();
+        assert_eq!(
+            out,
+            "127.0.0.1 localhost\r\n# focus-timer-block-start\r\n0.0.0.0 x.com\r\n:: x.com\r\n# focus-timer-block-end\r\n"
+        );
+        assert_eq!(strip_hosts_block(&out).unwrap(), text);
+    }
+
+    #[test]
+    fn round_trip_is_byte_exact() {
+        for text in [
+            "",
+            "127.0.0.1 localhost",
+            "127.0.0.1 localhost\r\n",
+            "# mixed\r\n127.0.0.1 localhost\n::1 localhost",
+            "127.0.0.1 localhost\n\n\n",
+        ] {
+            let blocked = append_hosts_block(text, &hosts(&["x.com"])).unwrap();
+            assert_eq!(strip_hosts_block(&blocked).unwrap(), text, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn missing_end_marker_is_an_error() {
+        let text = "127.0.0.1 localhost\n# focus-timer-block-start\n0.0.0.0 x.com\n10.0.0.2 nas\n";
+        assert!(strip_hosts_block(text).is_err());
+        assert!(append_hosts_block(text, &hosts(&["y.com"])).is_err());
+
+        let store = MemoryHostsStore::new(text);
+        let mut backend = DirectHosts::new(store.clone(), None);
+        assert!(backend.clear().is_err());
+        assert_eq!(store.contents(), text);
+    }
+
+    #[test]
+    fn append_replaces_existing_block() {
+        let once = append_hosts_block("127.0.0.1 localhost", &hosts(&["x.com"])).unwrap();
+        let twice = append_hosts_block(&once, &hosts(&["y.com"])).unwrap();
+        assert_eq!(twice.matches(HOSTS_START).count(), 1);
+        assert!(
+            twice.starts_with("127.0.0.1 localhost\n# focus-timer-block-start (added newline)\n")
+        );
+        assert!(!twice.contains("x.com"));
+        assert!(twice.contains("0.0.0.0 y.com"));
+    }
+
+    #[test]
+    fn damage_is_detected() {
+        assert_eq!(find_block_damage("127.0.0.1 localhost\n"), None);
+        assert!(find_block_damage("# focus-timer-block-start\n0.0.0.0 x.com\n").is_some());
+        assert!(find_block_damage("0.0.0.0 x.com\n# focus-timer-block-end\n").is_some());
+        assert!(find_block_damage(
+            "# focus-timer-block-start\n10.0.0.1 intranet\n# focus-timer-block-end\n"
+        )
+        .is_some());
+    }
+
+    #[test]
+    fn direct_hosts_round_trip_through_memory_store() {
+        let store = MemoryHostsStore::new("127.0.0.1 localhost\n");
+        let mut backend = DirectHosts::new(store.clone(), None);
+
+        backend.apply(&hosts(&["x.com"])).unwrap();
+        assert!(store.contents().contains("0.0.0.0 x.com"));
+        backend.clear().unwrap();
+        assert_eq!(store.contents(), "127.0.0.1 localhost\n");
+    }
+
+    #[test]
+    fn file_store_writes_atomically_to_arbitrary_path() {
+        let dir = std::env::temp_dir().join(format!("focus-hosts-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("hosts");
+        fs::write(&path, "127.0.0.1 localhost\n").unwrap();
+        #[cfg(unix)]
+        let inode = || std::os::unix::fs::MetadataExt::ino(&fs::metadata(&path).unwrap());
+        #[cfg(unix)]
+        let original = inode();
+
+        let mut backend = DirectHosts::new(FileHostsStore::new(&path), Some(dir.join("backups")));
+        backend.apply(&hosts(&["x.com"])).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().contains(":: x.com"));
+        // We own the file, so it was swapped for the temp file rather than rewritten.
+        #[cfg(unix)]
+        assert_ne!(inode(), original);
+        assert_eq!(backend.backups().len(), 1);
+        backend.clear().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "127.0.0.1 localhost\n");
+
+        let leftovers: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    /// Only the hosts file itself is writable, as `elevation_hint` suggests setting up.
+    #[cfg(unix)]
+    #[test]
+    fn file_store_writes_in_place_when_the_directory_is_read_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("focus-hosts-ro-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("hosts");
+        fs::write(&path, "127.0.0.1 localhost\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o646)).unwrap();
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o555)).unwrap();
+
+        let mut store = FileHostsStore::new(&path);
+        let result = store.write("127.0.0.1 localhost\n0.0.0.0 x.com\n");
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o755)).unwrap();
+
+        result.unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "127.0.0.1 localhost\n0.0.0.0 x.com\n"
+        );
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o646);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,143 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::process_blocker::SuspendedProcess;
+
+/// What the system looked like before focus mode changed it. Written to disk on every
+/// change so a crashed or killed app can put things back on the next start.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct JournalEntry {
+    /// Windows `ToastEnabled` registry value before it was switched off.
+    pub toast_suppressed: bool,
+    pub toast_prev: Option<u32>,
+    pub toast_prev_existed: bool,
+    /// GNOME `show-banners` before Do Not Disturb was turned on.
+    pub gnome_banners_prev: Option<bool>,
+    /// Copy of the hosts file taken before the focus block was added.
+    pub hosts_backup: Option<String>,
+    /// Applications frozen by the process blocker.
+    pub suspended_processes: Vec<SuspendedProcess>,
+}
+
+impl JournalEntry {
+    pub fn is_empty(&self) -> bool {
+        *self == JournalEntry::default()
+    }
+}
+
+/// Shared handle to the journal file; cheap to clone into each blocker.
+#[derive(Clone, Default)]
+pub struct Journal {
+    inner: Arc<Mutex<JournalState>>,
+}
+
+#[derive(Default)]
+struct JournalState {
+    /// `None` keeps the journal in memory only.
+    path: Option<PathBuf>,
+    entry: JournalEntry,
+}
+
+impl Journal {
+    /// Opens the journal at `path`, keeping whatever a previous run left in it.
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let entry = fs::read_to_string(&path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        Self {
+            inner: Arc::new(Mutex::new(JournalState {
+                path: Some(path),
+                entry,
+            })),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn entry(&self) -> JournalEntry {
+        self.inner
+            .lock()
+            .map(|state| state.entry.clone())
+            .unwrap_or_default()
+    }
+
+    /// Changes the entry and writes it out before returning.
+    pub fn update(&self, change: impl FnOnce(&mut JournalEntry)) -> Result<(), AppError> {
+        let mut state = self
+            .inner
+            .lock()
+            .map_err(|_| AppError::poisoned("journal"))?;
+        change(&mut state.entry);
+        match &state.path {
+            Some(path) => persist(path, &state.entry),
+            None => Ok(()),
+        }
+    }
+
+    /// Hands back everything recorded so far and starts over with an empty journal.
+    pub fn take(&self) -> JournalEntry {
+        let mut entry = JournalEntry::default();
+        let _ = self.update(|current| entry = std::mem::take(current));
+        entry
+    }
+}
+
+/// Writes via a temp file and rename so a crash mid-write can't leave half a journal.
+fn persist(path: &Path, entry: &JournalEntry) -> Result<(), AppError> {
+    if entry.is_empty() {
+        return match fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(AppError::io(format!("failed to remove {path:?}"), e))
+            }
+            _ => Ok(()),
+        };
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| AppError::io(format!("failed to create {dir:?}"), e))?;
+    }
+    let text = serde_json::to_string_pretty(entry).map_err(|e| AppError::Io {
+        message: format!("failed to encode journal: {e}"),
+    })?;
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, text).map_err(|e| AppError::io(format!("failed to write {tmp:?}"), e))?;
+    fs::rename(&tmp, path).map_err(|e| AppError::io(format!("failed to replace {path:?}"), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn survives_reopen_and_is_removed_once_empty() {
+        let dir = std::env::temp_dir().join(format!("focus-journal-{}", std::process::id()));
+        let path = dir.join("journal.json");
+        let _ = fs::remove_dir_all(&dir);
+
+        let journal = Journal::open(&path);
+        journal
+            .update(|entry| {
+                entry.toast_suppressed = true;
+                entry.toast_prev = Some(1);
+                entry.toast_prev_existed = true;
+            })
+            .unwrap();
+        assert!(path.exists());
+
+        // A new process sees what the old one recorded.
+        let reopened = Journal::open(&path);
+        let leftover = reopened.take();
+        assert_eq!(leftover.toast_prev, Some(1));
+        assert!(leftover.toast_prev_existed);
+        assert!(!path.exists());
+        assert!(reopened.entry().is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,464 @@
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use super::SiteRules;
+use crate::error::AppError;
+
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+
+/// Seconds left in the current work session, if one is running.
+pub type TimeLeft = Arc<dyn Fn() -> Option<u32> + Send + Sync>;
+
+/// Local HTTP proxy for focus mode. Plain HTTP requests to blocked hosts get a small page
+/// saying how long the session has left; `CONNECT` tunnels to them are refused with 403.
+/// Everything else is passed through untouched.
+pub struct BlockProxy {
+    #[cfg(test)]
+    local_addr: SocketAddr,
+    rules: Arc<RwLock<SiteRules>>,
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl BlockProxy {
+    pub fn start(
+        listen: SocketAddr,
+        rules: SiteRules,
+        time_left: Option<TimeLeft>,
+    ) -> Result<Self, AppError> {
+        let listener = TcpListener::bind(listen).map_err(|e| {
+            AppError::io(format!("failed to start the blocking proxy on {listen}"), e)
+        })?;
+        #[cfg(test)]
+        let local_addr = listener
+            .local_addr()
+            .map_err(|e| AppError::io("failed to read proxy listen address", e))?;
+        // Poll instead of blocking in accept() so `stop` is noticed promptly.
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| AppError::io("failed to configure the blocking proxy", e))?;
+
+        let rules = Arc::new(RwLock::new(rules));
+        let stop = Arc::new(AtomicBool::new(false));
+        let worker = {
+            let rules = rules.clone();
+            let stop = stop.clone();
+            thread::spawn(move || serve(listener, rules, time_left, stop))
+        };
+
+        Ok(Self {
+            #[cfg(test)]
+            local_addr,
+            rules,
+            stop,
+            worker: Some(worker),
+        })
+    }
+
+    #[cfg(test)]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    pub fn set_rules(&self, rules: SiteRules) {
+        if let Ok(mut current) = self.rules.write() {
+            *current = rules;
+        }
+    }
+
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for BlockProxy {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+fn serve(
+    listener: TcpListener,
+    rules: Arc<RwLock<SiteRules>>,
+    time_left: Option<TimeLeft>,
+    stop: Arc<AtomicBool>,
+) {
+    while !stop.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let rules = rules.clone();
+                let time_left = time_left.clone();
+                thread::spawn(move || {
+                    let _ = handle(stream, &rules, time_left.as_ref());
+                });
+            }
+            Err(_) => thread::sleep(Duration::from_millis(50)),
+        }
+    }
+}
+
+struct RequestHead {
+    method: String,
+    target: String,
+    version: String,
+    headers: Vec<(String, String)>,
+}
+
+fn handle(
+    mut client: TcpStream,
+    rules: &RwLock<SiteRules>,
+    time_left: Option<&TimeLeft>,
+) -> io::Result<()> {
+    client.set_nonblocking(false)?;
+    client.set_read_timeout(Some(Duration::from_secs(10)))?;
+
+    let (head, body_start) = read_head(&mut client)?;
+    let Some(request) = parse_head(&head) else {
+        return respond(&mut client, "400 Bad Request", "text/plain", "Bad request");
+    };
+
+    let is_connect = request.method.eq_ignore_ascii_case("CONNECT");
+    let target = if is_connect {
+        split_host_port(&request.target, 443).map(|(host, port)| (host, port, String::new()))
+    } else {
+        http_target(&request)
+    };
+    let Some((host, port, path)) = target else {
+        return respond(&mut client, "400 Bad Request", "text/plain", "Bad request");
+    };
+
+    let is_blocked = rules
+        .read()
+        .map(|rules| rules.is_blocked(&host))
+        .unwrap_or(false);
+    if is_blocked {
+        return if is_connect {
+            // Browsers don't render bodies of failed tunnels, so keep this one short.
+            respond(
+                &mut client,
+                "403 Forbidden",
+                "text/plain",
+                "Blocked during focus",
+            )
+        } else {
+            let secs = time_left.and_then(|time_left| time_left());
+            respond(
+                &mut client,
+                "403 Forbidden",
+                "text/html; charset=utf-8",
+                &blocked_page(&host, secs),
+            )
+        };
+    }
+
+    let mut upstream = match TcpStream::connect((host.as_str(), port)) {
+        Ok(stream) => stream,
+        Err(e) => {
+            return respond(
+                &mut client,
+                "502 Bad Gateway",
+                "text/plain",
+                &format!("Couldn't reach {host}: {e}"),
+            )
+        }
+    };
+
+    if is_connect {
+        client.write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")?;
+    } else {
+        // One request per connection keeps the proxy from having to parse responses.
+        let mut forwarded = format!("{} {path} {}\r\n", request.method, request.version);
+        for (name, value) in &request.headers {
+            let hop_by_hop = ["connection", "proxy-connection", "keep-alive"]
+                .iter()
+                .any(|h| name.eq_ignore_ascii_case(h));
+            if !hop_by_hop {
+                forwarded.push_str(&format!("{name}: {value}\r\n"));
+            }
+        }
+        forwarded.push_str("Connection: close\r\n\r\n");
+        upstream.write_all(forwarded.as_bytes())?;
+    }
+    upstream.write_all(&body_start)?;
+
+    client.set_read_timeout(None)?;
+    tunnel(client, upstream)
+}
+
+/// Reads up to the blank line ending the request head; returns the head and any bytes
+/// already received after it.
+fn read_head(stream: &mut TcpStream) -> io::Result<(Vec<u8>, Vec<u8>)> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            let rest = buf.split_off(end + 4);
+            return Ok((buf, rest));
+        }
+        if buf.len() > MAX_HEAD_BYTES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "request head too large",
+            ));
+        }
+        let n = stream.read(&mut chunk)?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+}
+
+fn parse_head(head: &[u8]) -> Option<RequestHead> {
+    let text = std::str::from_utf8(head).ok()?;
+    let mut lines = text.split("\r\n");
+    let mut parts = lines.next()?.split_whitespace();
+    let (method, target, version) = (parts.next()?, parts.next()?, parts.next()?);
+
+    let headers = lines
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (name, value) = line.split_once(':')?;
+            Some((name.trim().to_string(), value.trim().to_string()))
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(RequestHead {
+        method: method.to_string(),
+        target: target.to_string(),
+        version: version.to_string(),
+        headers,
+    })
+}
+
+/// `example.com:8080` / `[::1]:443` / `example.com` -> host and port.
+fn split_host_port(authority: &str, default_port: u16) -> Option<(String, u16)> {
+    let (host, port) = if let Some(rest) = authority.strip_prefix('[') {
+        let (host, after) = rest.split_once(']')?;
+        (host, after.strip_prefix(':'))
+    } else {
+        match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        }
+    };
+    let port = match port {
+        Some(port) => port.parse().ok()?,
+        None => default_port,
+    };
+    if host.is_empty() {
+        return None;
+    }
+    Some((host.to_ascii_lowercase(), port))
+}
+
+/// Host, port and origin-form path of a plain HTTP proxy request.
+fn http_target(request: &RequestHead) -> Option<(String, u16, String)> {
+    if let Some(rest) = request.target.strip_prefix("http://") {
+        let (authority, path) = match rest.find('/') {
+            Some(idx) => (&rest[..idx], &rest[idx..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = split_host_port(authority, 80)?;
+        return Some((host, port, path.to_string()));
+    }
+
+    // Origin-form request (e.g. a transparent redirect): fall back to the Host header.
+    let host_header = request
+        .headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("host"))?;
+    let (host, port) = split_host_port(&host_header.1, 80)?;
+    Some((host, port, request.target.clone()))
+}
+
+fn respond(client: &mut TcpStream, status: &str, content_type: &str, body: &str) -> io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    client.write_all(response.as_bytes())?;
+    client.flush()
+}
+
+fn tunnel(client: TcpStream, upstream: TcpStream) -> io::Result<()> {
+    let mut client_read = client.try_clone()?;
+    let mut upstream_write = upstream.try_clone()?;
+    let outbound = thread::spawn(move || {
+        let _ = io::copy(&mut client_read, &mut upstream_write);
+        let _ = upstream_write.shutdown(Shutdown::Write);
+    });
+
+    let (mut upstream_read, mut client_write) = (upstream, client);
+    let _ = io::copy(&mut upstream_read, &mut client_write);
+    let _ = client_write.shutdown(Shutdown::Write);
+    let _ = outbound.join();
+    Ok(())
+}
+
+fn format_time_left(secs: u32) -> String {
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+fn blocked_page(host: &str, time_left: Option<u32>) -> String {
+    let headline = match time_left {
+        Some(secs) => format!("Blocked during focus — {} left", format_time_left(secs)),
+        None => "Blocked during focus".to_string(),
+    };
+    let host: String = host
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':'))
+        .collect();
+    format!(
+        "<!doctype html>
+<html>
+<head><meta charset=\"utf-8\"><title>{headline}</title></head>
+<body style=\"font-family: sans-serif; text-align: center; margin-top: 20vh\">
+<h1>{headline}</h1>
+<p>{host} is on your blocklist until the work session ends.</p>
+</body>
+</html>
+"
+    )
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    fn start(blocked: &[&str]) -> BlockProxy {
+        BlockProxy::start(
+            "127.0.0.1:0".parse().unwrap(),
+            SiteRules {
+                domains: blocked.iter().map(|d| d.to_string()).collect(),
+                allowlist: false,
+            },
+            Some(Arc::new(|| Some(754))),
+        )
+        .unwrap()
+    }
+
+    fn send(proxy: &BlockProxy, request: &str) -> (TcpStream, String) {
+        connect_and_send(proxy.local_addr(), request)
+    }
+
+    /// Sends `request` to the proxy at `addr` and returns the response head.
+    pub(in crate::focus_blocker) fn send_to(addr: SocketAddr, request: &str) -> String {
+        connect_and_send(addr, request).1
+    }
+
+    fn connect_and_send(addr: SocketAddr, request: &str) -> (TcpStream, String) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let (head, _) = read_head(&mut stream).unwrap();
+        (stream, String::from_utf8(head).unwrap())
+    }
+
+    fn send_and_read_all(proxy: &BlockProxy, request: &str) -> String {
+        let mut stream = TcpStream::connect(proxy.local_addr()).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut out = String::new();
+        stream.read_to_string(&mut out).unwrap();
+        out
+    }
+
+    /// Stand-in origin server that echoes whatever it receives.
+    pub(in crate::focus_blocker) fn echo_server() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                thread::spawn(move || {
+                    let mut writer = stream.try_clone().unwrap();
+                    let _ = io::copy(&mut stream, &mut writer);
+                });
+            }
+        });
+        addr
+    }
+
+    #[test]
+    fn splits_authorities() {
+        assert_eq!(
+            split_host_port("Example.com:8080", 80),
+            Some(("example.com".to_string(), 8080))
+        );
+        assert_eq!(
+            split_host_port("example.com", 443),
+            Some(("example.com".to_string(), 443))
+        );
+        assert_eq!(
+            split_host_port("[::1]:443", 80),
+            Some(("::1".to_string(), 443))
+        );
+        assert_eq!(split_host_port("example.com:http", 80), None);
+    }
+
+    #[test]
+    fn plain_http_to_blocked_host_gets_time_left_page() {
+        let proxy = start(&["reddit.com"]);
+        let response = send_and_read_all(
+            &proxy,
+            "GET http://www.reddit.com/r/rust HTTP/1.1\r\nHost: www.reddit.com\r\n\r\n",
+        );
+        assert!(response.starts_with("HTTP/1.1 403 Forbidden\r\n"));
+        assert!(response.contains("text/html"));
+        assert!(response.contains("Blocked during focus — 12:34 left"));
+        assert!(response.contains("www.reddit.com"));
+    }
+
+    #[test]
+    fn connect_to_blocked_host_is_refused() {
+        let proxy = start(&["reddit.com"]);
+        let (_, head) = send(
+            &proxy,
+            "CONNECT reddit.com:443 HTTP/1.1\r\nHost: reddit.com:443\r\n\r\n",
+        );
+        assert!(head.starts_with("HTTP/1.1 403 Forbidden\r\n"));
+    }
+
+    #[test]
+    fn connect_to_allowed_host_is_tunneled() {
+        let origin = echo_server();
+        let proxy = start(&["reddit.com"]);
+        let (mut stream, head) = send(&proxy, &format!("CONNECT {origin} HTTP/1.1\r\n\r\n"));
+        assert!(head.starts_with("HTTP/1.1 200"));
+
+        stream.write_all(b"ping").unwrap();
+        let mut reply = [0u8; 4];
+        stream.read_exact(&mut reply).unwrap();
+        assert_eq!(&reply, b"ping");
+    }
+
+    #[test]
+    fn plain_http_to_allowed_host_is_forwarded_in_origin_form() {
+        let origin = echo_server();
+        let proxy = start(&["reddit.com"]);
+        // The echo server sends the forwarded request back as the "response".
+        let (stream, head) = send(
+            &proxy,
+            &format!(
+                "GET http://{origin}/docs?q=1 HTTP/1.1\r\nHost: {origin}\r\nProxy-Connection: keep-alive\r\n\r\n"
+            ),
+        );
+        assert!(head.starts_with("GET /docs?q=1 HTTP/1.1\r\n"));
+        assert!(head.contains("Connection: close"));
+        assert!(!head.contains("Proxy-Connection"));
+        drop(stream);
+    }
+}
@@ -0,0 +1,381 @@
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::focus_blocker::{is_valid_hostname, DirectHosts, HostsBackend};
+
+/// Address the helper listens on; overridable so several users/tests don't collide.
+pub const HELPER_ADDR_ENV: &str = "FOCUS_TIMER_HELPER_ADDR";
+pub const DEFAULT_HELPER_ADDR: &str = "127.0.0.1:47613";
+/// Path of the shared key the helper checks every request against. The app creates the file
+/// (see [`load_or_create_key`]); the helper only reads it.
+pub const HELPER_KEY_ENV: &str = "FOCUS_TIMER_HELPER_KEY_FILE";
+/// Name of the key file in the app data dir.
+pub const HELPER_KEY_FILE: &str = "hosts-helper.key";
+
+const MAX_REQUEST_BYTES: u64 = 256 * 1024;
+const MAX_HOSTS: usize = 5000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum HelperRequest {
+    Apply { hosts: Vec<String> },
+    Clear,
+}
+
+/// What goes over the wire: the request plus the shared key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KeyedRequest {
+    key: String,
+    #[serde(flatten)]
+    request: HelperRequest,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HelperResponse {
+    pub ok: bool,
+    #[serde(default)]
+    pub error: Option<AppError>,
+}
+
+pub fn helper_addr() -> Result<SocketAddr, AppError> {
+    let raw = std::env::var(HELPER_ADDR_ENV).unwrap_or_else(|_| DEFAULT_HELPER_ADDR.to_string());
+    let addr: SocketAddr = raw.parse().map_err(|e| {
+        AppError::validation(
+            HELPER_ADDR_ENV,
+            format!("invalid helper address {raw:?}: {e}"),
+        )
+    })?;
+    if !addr.ip().is_loopback() {
+        return Err(AppError::validation(
+            HELPER_ADDR_ENV,
+            format!("helper address {addr} must be a loopback address"),
+        ));
+    }
+    Ok(addr)
+}
+
+/// Reads the shared key at `path`, generating it on first use. The file is only readable by
+/// the current user: mode 0600 on Unix; on Windows the app data dir already restricts it to
+/// the user (and administrators, which the elevated helper runs as).
+pub fn load_or_create_key(path: &Path) -> Result<String, AppError> {
+    match fs::read_to_string(path) {
+        Ok(key) if !key.trim().is_empty() => return Ok(key.trim().to_string()),
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => {
+            return Err(AppError::io(
+                format!("failed to read {}", path.display()),
+                e,
+            ))
+        }
+    }
+
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes).map_err(|e| AppError::Io {
+        message: format!("failed to generate the hosts helper key: {e}"),
+    })?;
+    let key: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| AppError::io(format!("failed to create {}", dir.display()), e))?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(key.as_bytes()))
+        .map_err(|e| AppError::io(format!("failed to write {}", path.display()), e))?;
+    Ok(key)
+}
+
+/// The key file named by [`HELPER_KEY_ENV`]; the helper won't start without one.
+fn read_key_from_env() -> Result<String, AppError> {
+    let path = std::env::var_os(HELPER_KEY_ENV).ok_or_else(|| {
+        AppError::validation(
+            HELPER_KEY_ENV,
+            format!("set {HELPER_KEY_ENV} to the app's {HELPER_KEY_FILE}"),
+        )
+    })?;
+    let path = Path::new(&path);
+    let key = fs::read_to_string(path)
+        .map_err(|e| AppError::io(format!("failed to read {}", path.display()), e))?;
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(AppError::validation(
+            HELPER_KEY_ENV,
+            format!("{} is empty", path.display()),
+        ));
+    }
+    Ok(key.to_string())
+}
+
+/// Compares without stopping at the first mismatch, so response timing doesn't leak the key.
+fn keys_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Client side used by the app: forwards apply/clear to the elevated helper process.
+#[derive(Debug, Clone)]
+pub struct HelperHostsBackend {
+    addr: SocketAddr,
+    key: String,
+}
+
+impl HelperHostsBackend {
+    pub fn new(addr: SocketAddr, key: String) -> Self {
+        Self { addr, key }
+    }
+
+    fn send(&self, request: &HelperRequest) -> Result<(), AppError> {
+        let unreachable = |e: std::io::Error| {
+            AppError::io(
+                format!(
+                    "couldn't reach the hosts helper at {} (is focus-hosts-helper running elevated?)",
+                    self.addr
+                ),
+                e,
+            )
+        };
+
+        let mut stream =
+            TcpStream::connect_timeout(&self.addr, Duration::from_secs(2)).map_err(unreachable)?;
+        stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .map_err(unreachable)?;
+
+        let keyed = KeyedRequest {
+            key: self.key.clone(),
+            request: request.clone(),
+        };
+        let mut line = serde_json::to_string(&keyed).map_err(|e| AppError::Io {
+            message: format!("failed to encode helper request: {e}"),
+        })?;
+        line.push('\n');
+        stream.write_all(line.as_bytes()).map_err(unreachable)?;
+
+        let mut reply = String::new();
+        BufReader::new(stream)
+            .take(MAX_REQUEST_BYTES)
+            .read_line(&mut reply)
+            .map_err(unreachable)?;
+        let response: HelperResponse = serde_json::from_str(&reply).map_err(|e| AppError::Io {
+            message: format!("invalid response from hosts helper: {e}"),
+        })?;
+
+        if response.ok {
+            Ok(())
+        } else {
+            Err(response.error.unwrap_or_else(|| AppError::Io {
+                message: "hosts helper rejected the request".to_string(),
+            }))
+        }
+    }
+}
+
+impl HostsBackend for HelperHostsBackend {
+    fn apply(&mut self, hosts: &[String]) -> Result<(), AppError> {
+        self.send(&HelperRequest::Apply {
+            hosts: hosts.to_vec(),
+        })
+    }
+
+    fn clear(&mut self) -> Result<(), AppError> {
+        self.send(&HelperRequest::Clear)
+    }
+}
+
+/// Rejects anything that isn't a bounded list of plain host names, so a local caller can
+/// only ever change the lines between the focus-timer markers.
+fn validate_request(request: &HelperRequest) -> Result<(), AppError> {
+    if let HelperRequest::Apply { hosts } = request {
+        if hosts.len() > MAX_HOSTS {
+            return Err(AppError::validation(
+                "hosts",
+                format!("too many hosts (max {MAX_HOSTS})"),
+            ));
+        }
+        if let Some(bad) = hosts.iter().find(|h| !is_valid_hostname(h)) {
+            return Err(AppError::validation(
+                "hosts",
+                format!("invalid host name {bad:?}"),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    hosts: &mut dyn HostsBackend,
+    key: &str,
+) -> Result<(), AppError> {
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .map_err(|e| AppError::io("failed to configure connection", e))?;
+    let mut writer = stream
+        .try_clone()
+        .map_err(|e| AppError::io("failed to configure connection", e))?;
+
+    let mut line = String::new();
+    BufReader::new(stream)
+        .take(MAX_REQUEST_BYTES)
+        .read_line(&mut line)
+        .map_err(|e| AppError::io("failed to read request", e))?;
+
+    let result = serde_json::from_str::<KeyedRequest>(&line)
+        .map_err(|e| AppError::validation("request", format!("malformed request: {e}")))
+        .and_then(
+            |KeyedRequest {
+                 key: given,
+                 request,
+             }| {
+                if !keys_match(&given, key) {
+                    return Err(AppError::PermissionDenied {
+                        message: "the hosts helper key doesn't match".to_string(),
+                    });
+                }
+                validate_request(&request)?;
+                match request {
+                    HelperRequest::Apply { hosts: list } => hosts.apply(&list),
+                    HelperRequest::Clear => hosts.clear(),
+                }
+            },
+        );
+
+    let response = match result {
+        Ok(()) => HelperResponse {
+            ok: true,
+            error: None,
+        },
+        Err(e) => HelperResponse {
+            ok: false,
+            error: Some(e),
+        },
+    };
+    let mut reply = serde_json::to_string(&response).map_err(|e| AppError::Io {
+        message: format!("failed to encode response: {e}"),
+    })?;
+    reply.push('\n');
+    writer
+        .write_all(reply.as_bytes())
+        .map_err(|e| AppError::io("failed to write response", e))
+}
+
+/// Serves requests one at a time so hosts file edits never interleave. Requests that don't
+/// carry `key` are refused.
+pub fn serve(
+    listener: TcpListener,
+    hosts: &mut dyn HostsBackend,
+    key: &str,
+) -> Result<(), AppError> {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(e) = handle_connection(stream, hosts, key) {
+                    eprintln!("focus-hosts-helper: {e}");
+                }
+            }
+            Err(e) => eprintln!("focus-hosts-helper: failed to accept connection: {e}"),
+        }
+    }
+    Ok(())
+}
+
+/// Entry point of the `focus-hosts-helper` binary. The hosts file is resolved from the
+/// helper's own environment; clients can't choose which file gets edited.
+pub fn run() -> Result<(), AppError> {
+    let addr = helper_addr()?;
+    let key = read_key_from_env()?;
+    let listener = TcpListener::bind(addr)
+        .map_err(|e| AppError::io(format!("failed to listen on {addr}"), e))?;
+    let mut hosts = DirectHosts::system(None, None);
+    eprintln!(
+        "focus-hosts-helper: listening on {addr}, editing {}",
+        hosts.location()
+    );
+
+    // Never leave a block behind from a previous run of the app, but don't touch a damaged one.
+    match hosts.recover() {
+        Ok(Some(recovery)) => eprintln!(
+            "focus-hosts-helper: leaving {} untouched: {}",
+            recovery.hosts_path, recovery.reason
+        ),
+        Ok(None) => {}
+        Err(e) => eprintln!("focus-hosts-helper: {e}"),
+    }
+    serve(listener, &mut hosts, &key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::focus_blocker::MemoryHostsStore;
+
+    const KEY: &str = "0123456789abcdef";
+
+    /// Runs a helper over an in-memory hosts file on a free port.
+    fn spawn_helper(store: MemoryHostsStore) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let mut hosts = DirectHosts::new(store, None);
+            serve(listener, &mut hosts, KEY)
+        });
+        addr
+    }
+
+    #[test]
+    fn requests_need_the_shared_key() {
+        let store = MemoryHostsStore::new("127.0.0.1 localhost\n");
+        let addr = spawn_helper(store.clone());
+        let hosts = vec!["reddit.com".to_string()];
+
+        let mut intruder = HelperHostsBackend::new(addr, "not-the-key".to_string());
+        assert!(matches!(
+            intruder.apply(&hosts),
+            Err(AppError::PermissionDenied { .. })
+        ));
+        assert_eq!(store.contents(), "127.0.0.1 localhost\n");
+
+        let mut app = HelperHostsBackend::new(addr, KEY.to_string());
+        app.apply(&hosts).unwrap();
+        assert!(store.contents().contains("0.0.0.0 reddit.com"));
+        app.clear().unwrap();
+        assert_eq!(store.contents(), "127.0.0.1 localhost\n");
+    }
+
+    #[test]
+    fn key_is_created_once_and_private() {
+        let dir = std::env::temp_dir().join(format!("focus-helper-key-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join(HELPER_KEY_FILE);
+
+        let key = load_or_create_key(&path).unwrap();
+        assert_eq!(key.len(), 64);
+        assert_eq!(load_or_create_key(&path).unwrap(), key);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,8 +1,17 @@
+mod clock;
 mod database;
+mod error;
 mod focus_blocker;
+mod hosts_helper;
+mod migrations;
 mod models;
+mod process_blocker;
+mod schedule;
+mod session_lock;
 mod timer;
 
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc, Mutex,
@@ -10,11 +19,24 @@ use std::sync::{
 use std::thread;
 use std::time::Duration;
 
+use chrono::{DateTime, Utc};
 use tauri::{AppHandle, Emitter, Manager, State, WindowEvent};
 
+use clock::{Clock, SharedClock};
 use database::Database;
-use focus_blocker::{emit_blocking_error, FocusBlocker};
-use models::{Settings, TodayStatistics};
+use error::AppError;
+use focus_blocker::{
+    normalize_domain, normalize_subdomain, BlockMethod, BlockerStatus, DirectHosts, FocusBlocker,
+    HostsBackend, HostsRecovery, Journal, TimeLeft,
+};
+use hosts_helper::HelperHostsBackend;
+use models::{
+    BlockMode, BlockSchedule, BlockedApp, BlockedSite, BlockingMethod, BlocklistProfile, Settings,
+    TodayStatistics,
+};
+use process_blocker::{normalize_app_name, ProcessBlocker};
+use schedule::{normalize_schedule, BlockingHolds, HoldSource};
+use session_lock::{locked_error, SessionLock, SessionLockStatus};
 use timer::{Phase, PhaseCompleteEvent, TimerConfig, TimerState};
 
 #[derive(Clone)]
@@ -22,18 +44,35 @@ struct TimerManager {
     cfg: Arc<Mutex<TimerConfig>>,
     state: Arc<Mutex<TimerState>>,
     worker_running: Arc<AtomicBool>,
+    /// Set while a work session started in locked mode is running; mirrored in the database.
+    session_lock: Arc<Mutex<Option<SessionLock>>>,
+    clock: SharedClock,
 }
 
 impl TimerManager {
-    fn new(cfg: TimerConfig) -> Self {
+    fn new(cfg: TimerConfig, clock: SharedClock) -> Self {
         Self {
             cfg: Arc::new(Mutex::new(cfg)),
             state: Arc::new(Mutex::new(TimerState::new(cfg))),
             worker_running: Arc::new(AtomicBool::new(false)),
+            session_lock: Arc::new(Mutex::new(None)),
+            clock,
         }
     }
 }
 
+/// Remaining seconds of the running work session, for the proxy's "blocked" page.
+fn time_left_from_timer(manager: &TimerManager) -> TimeLeft {
+    let state = manager.state.clone();
+    Arc::new(move || {
+        state
+            .lock()
+            .ok()
+            .filter(|s| s.phase == Phase::Work)
+            .map(|s| s.remaining_seconds)
+    })
+}
+
 fn emit_timer_state(app: &AppHandle, state: &TimerState) {
     let _ = app.emit("timer-state", state);
 }
@@ -46,6 +85,78 @@ fn emit_today_statistics(app: &AppHandle, stats: &TodayStatistics) {
     let _ = app.emit("today-statistics", stats);
 }
 
+fn emit_blocker_status(app: &AppHandle, status: &BlockerStatus) {
+    let _ = app.emit("blocker-status", status);
+}
+
+fn emit_session_lock(app: &AppHandle, status: Option<&SessionLockStatus>) {
+    let _ = app.emit("session-lock", status);
+}
+
+/// The locked session in effect, if any. A lock whose work phase is already over is
+/// dropped on the way.
+fn current_session_lock(
+    app: &AppHandle,
+    manager: &TimerManager,
+) -> Result<Option<SessionLock>, AppError> {
+    let expired = manager
+        .session_lock
+        .lock()
+        .map_err(|_| AppError::poisoned("session lock"))?
+        .as_ref()
+        .is_some_and(|lock| lock.is_expired(manager.clock.now()));
+    if expired {
+        set_session_lock(app, manager, None)?;
+    }
+    manager
+        .session_lock
+        .lock()
+        .map(|lock| lock.clone())
+        .map_err(|_| AppError::poisoned("session lock"))
+}
+
+/// Stores the lock in memory and in the database (so a restart can't bypass it) and tells
+/// the UI.
+fn set_session_lock(
+    app: &AppHandle,
+    manager: &TimerManager,
+    lock: Option<SessionLock>,
+) -> Result<(), AppError> {
+    if let Some(db) = app.try_state::<Database>() {
+        match &lock {
+            Some(lock) => db.save_session_lock(lock)?,
+            None => db.clear_session_lock()?,
+        }
+    }
+    emit_session_lock(app, lock.as_ref().map(SessionLock::status).as_ref());
+    *manager
+        .session_lock
+        .lock()
+        .map_err(|_| AppError::poisoned("session lock"))? = lock;
+    Ok(())
+}
+
+fn ensure_unlocked(app: &AppHandle, manager: &TimerManager, action: &str) -> Result<(), AppError> {
+    match current_session_lock(app, manager)? {
+        Some(_) => Err(locked_error(action)),
+        None => Ok(()),
+    }
+}
+
+/// Whether saving `new` would change how or what gets blocked, which a locked session
+/// doesn't allow.
+fn changes_blocking(old: &Settings, new: &Settings) -> bool {
+    old.block_mode != new.block_mode
+        || old.blocking_method != new.blocking_method
+        || old.use_hosts_helper != new.use_hosts_helper
+        || old.hosts_path != new.hosts_path
+        || old.dns_listen_addr != new.dns_listen_addr
+        || old.dns_upstream != new.dns_upstream
+        || old.proxy_listen_addr != new.proxy_listen_addr
+        || old.active_profile_id != new.active_profile_id
+        || old.block_subdomains != new.block_subdomains
+}
+
 fn timer_config_from_settings(settings: &Settings) -> TimerConfig {
     TimerConfig {
         work_seconds: settings.work_minutes.saturating_mul(60),
@@ -56,24 +167,177 @@ fn timer_config_from_settings(settings: &Settings) -> TimerConfig {
     }
 }
 
+fn enable_blocking(app: &AppHandle, db: &Database, blocker: &FocusBlocker) {
+    enable_site_blocking(db, blocker);
+    if let Some(processes) = app.try_state::<ProcessBlocker>() {
+        enable_app_blocking(db, blocker, processes.inner());
+    }
+}
+
+/// Failures reach the UI through the blocker's `blocker-status` event.
+fn enable_site_blocking(db: &Database, blocker: &FocusBlocker) {
+    match db.load_active_blocklist() {
+        Ok(blocklist) => {
+            let _ = blocker.enable(&blocklist);
+        }
+        Err(e) => blocker.report_error(e.context("couldn't load the blocklist")),
+    }
+}
+
+fn enable_app_blocking(db: &Database, blocker: &FocusBlocker, processes: &ProcessBlocker) {
+    if !process_blocker::SUPPORTED {
+        return;
+    }
+    let result = db.load_settings().and_then(|settings| {
+        let names = db
+            .list_blocked_apps()?
+            .into_iter()
+            .filter(|blocked| blocked.enabled)
+            .map(|blocked| blocked.name)
+            .collect();
+        processes.enable(names, settings.app_block_action)
+    });
+    if let Err(e) = result {
+        blocker.report_error(e.context("application blocking couldn't be enabled"));
+    }
+}
+
+/// Adds `source`'s hold on blocking and (re)applies it.
+fn hold_blocking(app: &AppHandle, db: &Database, blocker: &FocusBlocker, source: HoldSource) {
+    let enable = || enable_blocking(app, db, blocker);
+    match app.try_state::<BlockingHolds>() {
+        Some(holds) => {
+            if let Err(e) = holds.hold(source, enable) {
+                blocker.report_error(e);
+            }
+        }
+        None => enable(),
+    }
+}
+
+/// Drops `source`'s hold; blocking only goes off once nothing else holds it, so a schedule
+/// window outlasts a break and vice versa.
+fn release_blocking(app: &AppHandle, blocker: &FocusBlocker, source: HoldSource) {
+    let disable = || disable_blocking(app, blocker);
+    match app.try_state::<BlockingHolds>() {
+        Some(holds) => {
+            if let Err(e) = holds.release(source, disable) {
+                blocker.report_error(e);
+            }
+        }
+        None => disable(),
+    }
+}
+
+fn disable_blocking(app: &AppHandle, blocker: &FocusBlocker) {
+    let _ = blocker.disable();
+    if let Some(processes) = app.try_state::<ProcessBlocker>() {
+        if let Err(e) = processes.inner().disable() {
+            blocker.report_error(e.context("failed to stop application blocking"));
+        }
+    }
+}
+
+fn hosts_path_from_settings(settings: &Settings) -> Option<PathBuf> {
+    settings
+        .hosts_path
+        .as_deref()
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+fn hosts_backend_from_settings(
+    app: &AppHandle,
+    settings: &Settings,
+) -> Result<Box<dyn HostsBackend>, AppError> {
+    let data_dir = app.path().app_data_dir().ok();
+    if settings.use_hosts_helper {
+        let data_dir = data_dir.ok_or_else(|| AppError::not_found("app data dir not available"))?;
+        let key = hosts_helper::load_or_create_key(&data_dir.join(hosts_helper::HELPER_KEY_FILE))?;
+        Ok(Box::new(HelperHostsBackend::new(
+            hosts_helper::helper_addr()?,
+            key,
+        )))
+    } else {
+        let backup_dir = data_dir.map(|dir| dir.join("hosts-backups"));
+        Ok(Box::new(DirectHosts::system(
+            hosts_path_from_settings(settings).as_deref(),
+            backup_dir,
+        )))
+    }
+}
+
+fn block_method_from_settings(settings: &Settings) -> Result<BlockMethod, AppError> {
+    match settings.blocking_method {
+        BlockingMethod::Hosts => Ok(BlockMethod::Hosts),
+        BlockingMethod::Dns => {
+            let listen: SocketAddr = settings.dns_listen_addr.trim().parse().map_err(|_| {
+                AppError::validation(
+                    "dnsListenAddr",
+                    "dnsListenAddr must be an address like 127.0.0.1:53",
+                )
+            })?;
+            if !listen.ip().is_loopback() {
+                return Err(AppError::validation(
+                    "dnsListenAddr",
+                    "dnsListenAddr must be a loopback address",
+                ));
+            }
+            let upstream: SocketAddr = settings.dns_upstream.trim().parse().map_err(|_| {
+                AppError::validation(
+                    "dnsUpstream",
+                    "dnsUpstream must be an address like 1.1.1.1:53",
+                )
+            })?;
+            if upstream == listen {
+                return Err(AppError::validation(
+                    "dnsUpstream",
+                    "dnsUpstream can't be the sinkhole's own address",
+                ));
+            }
+            Ok(BlockMethod::Dns { listen, upstream })
+        }
+        BlockingMethod::Proxy => {
+            let listen: SocketAddr = settings.proxy_listen_addr.trim().parse().map_err(|_| {
+                AppError::validation(
+                    "proxyListenAddr",
+                    "proxyListenAddr must be an address like 127.0.0.1:8899",
+                )
+            })?;
+            if !listen.ip().is_loopback() {
+                return Err(AppError::validation(
+                    "proxyListenAddr",
+                    "proxyListenAddr must be a loopback address",
+                ));
+            }
+            Ok(BlockMethod::Proxy { listen })
+        }
+    }
+}
+
+/// Points the blocker at the hosts backend and blocking method chosen in settings.
+fn configure_blocker(
+    app: &AppHandle,
+    blocker: &FocusBlocker,
+    settings: &Settings,
+) -> Result<(), AppError> {
+    blocker.set_hosts_backend(hosts_backend_from_settings(app, settings)?)?;
+    blocker.set_block_method(block_method_from_settings(settings)?)
+}
+
 fn spawn_timer_worker(app: AppHandle, manager: TimerManager, db: Database, blocker: FocusBlocker) {
     thread::spawn(move || {
+        let mut last_remaining = None;
         loop {
             let (snapshot, phase_complete, running) = {
-                let cfg = manager
-                    .cfg
-                    .lock()
-                    .expect("timer cfg mutex poisoned")
-                    .clone();
-                let mut state = manager
-                    .state
-                    .lock()
-                    .expect("timer state mutex poisoned");
+                let cfg = *manager.cfg.lock().expect("timer cfg mutex poisoned");
+                let mut state = manager.state.lock().expect("timer state mutex poisoned");
 
                 if !state.is_running {
                     (state.clone(), None, false)
                 } else {
-                    let evt = state.tick(cfg);
+                    let evt = state.tick(cfg, manager.clock.now());
                     (state.clone(), evt, true)
                 }
             };
@@ -82,39 +346,105 @@ fn spawn_timer_worker(app: AppHandle, manager: TimerManager, db: Database, block
                 break;
             }
 
+            let phase_changed = phase_complete.is_some();
             if let Some(evt) = phase_complete {
-                if evt.to == Phase::Work {
-                    if let Err(e) = blocker.enable() {
-                        emit_blocking_error(
-                            &app,
-                            format!(
-                                "Focus-mode blocking couldn't be enabled (try running the app as Administrator): {e}"
-                            ),
-                        );
-                    }
-                } else if let Err(e) = blocker.disable() {
-                    emit_blocking_error(&app, format!("Failed to disable focus-mode blocking: {e}"));
+                if evt.from == Phase::Work {
+                    // The locked session has run its course.
+                    let _ = set_session_lock(&app, &manager, None);
+                }
+                // After a suspend the timer may have stopped instead of starting the next phase.
+                if snapshot.is_running && snapshot.phase == Phase::Work {
+                    hold_blocking(&app, &db, &blocker, HoldSource::Timer);
+                } else {
+                    release_blocking(&app, &blocker, HoldSource::Timer);
                 }
 
-                let _ = db.insert_completed_phase(evt.from, evt.completed_seconds);
+                let _ = db.insert_completed_phase(evt.from, evt.completed_seconds, evt.ended_at);
                 if let Ok(stats) = db.get_today_statistics() {
                     emit_today_statistics(&app, &stats);
                 }
                 emit_phase_complete(&app, &evt);
             }
-            emit_timer_state(&app, &snapshot);
+            // The clock is polled more often than once a second so the display doesn't skip
+            // a second when a tick lands late; the UI only hears about actual changes.
+            if phase_changed || last_remaining != Some(snapshot.remaining_seconds) {
+                last_remaining = Some(snapshot.remaining_seconds);
+                emit_timer_state(&app, &snapshot);
+            }
 
-            thread::sleep(Duration::from_secs(1));
+            thread::sleep(Duration::from_millis(250));
         }
 
         manager.worker_running.store(false, Ordering::SeqCst);
     });
 }
 
+/// Brings the schedule's hold in line with the local clock.
+fn apply_schedules(app: &AppHandle) {
+    let (Some(db), Some(blocker), Some(holds), Some(manager)) = (
+        app.try_state::<Database>(),
+        app.try_state::<FocusBlocker>(),
+        app.try_state::<BlockingHolds>(),
+        app.try_state::<TimerManager>(),
+    ) else {
+        return;
+    };
+    let result = sync_schedule_hold(
+        db.inner(),
+        holds.inner(),
+        manager.clock.as_ref(),
+        || enable_blocking(app, db.inner(), blocker.inner()),
+        || disable_blocking(app, blocker.inner()),
+    );
+    if let Err(e) = result {
+        blocker.report_error(e);
+    }
+}
+
+/// Takes or drops [`HoldSource::Schedule`] depending on whether a schedule window is open at
+/// `clock`'s local time; `disable` only runs once no other source holds blocking.
+fn sync_schedule_hold(
+    db: &Database,
+    holds: &BlockingHolds,
+    clock: &dyn Clock,
+    enable: impl FnOnce(),
+    disable: impl FnOnce(),
+) -> Result<(), AppError> {
+    let schedules = db
+        .list_block_schedules()
+        .map_err(|e| e.context("couldn't load blocking schedules"))?;
+    let active = schedule::any_active(&schedules, clock.local_now());
+    let held = holds.is_held_by(HoldSource::Schedule);
+    if active && !held {
+        holds.hold(HoldSource::Schedule, enable)
+    } else if !active && held {
+        holds.release(HoldSource::Schedule, disable)
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks the schedules every few seconds for as long as the app runs; independent of the
+/// timer worker, which only runs while the timer does.
+fn spawn_scheduler(app: AppHandle) {
+    thread::spawn(move || loop {
+        apply_schedules(&app);
+        thread::sleep(Duration::from_secs(15));
+    });
+}
+
 #[tauri::command]
-fn initialize_timer(app: AppHandle, manager: State<'_, TimerManager>) -> Result<TimerState, String> {
+fn initialize_timer(
+    app: AppHandle,
+    manager: State<'_, TimerManager>,
+) -> Result<TimerState, AppError> {
     if let Some(blocker) = app.try_state::<FocusBlocker>() {
-        let _ = blocker.inner().disable();
+        if current_session_lock(&app, &manager)?.is_none() {
+            release_blocking(&app, blocker.inner(), HoldSource::Timer);
+        }
+        if let Some(recovery) = blocker.inner().hosts_recovery() {
+            let _ = app.emit("hosts-recovery-needed", recovery);
+        }
     }
     let snapshot = get_timer_state(manager)?;
     emit_timer_state(&app, &snapshot);
@@ -122,48 +452,72 @@ fn initialize_timer(app: AppHandle, manager: State<'_, TimerManager>) -> Result<
 }
 
 #[tauri::command]
-fn get_timer_state(manager: State<'_, TimerManager>) -> Result<TimerState, String> {
+fn get_timer_state(manager: State<'_, TimerManager>) -> Result<TimerState, AppError> {
     let state = manager
         .state
         .lock()
-        .map_err(|_| "timer state mutex poisoned".to_string())?;
+        .map_err(|_| AppError::poisoned("timer state"))?;
     Ok(state.clone())
 }
 
 #[tauri::command]
-fn start_timer(app: AppHandle, manager: State<'_, TimerManager>) -> Result<(), String> {
+fn start_timer(
+    app: AppHandle,
+    manager: State<'_, TimerManager>,
+    locked: Option<bool>,
+) -> Result<(), AppError> {
+    if locked.unwrap_or(false) {
+        let (phase, remaining) = {
+            let state = manager
+                .state
+                .lock()
+                .map_err(|_| AppError::poisoned("timer state"))?;
+            (state.phase, state.remaining_seconds)
+        };
+        if phase != Phase::Work {
+            return Err(AppError::validation(
+                "locked",
+                "only work sessions can be locked",
+            ));
+        }
+        set_session_lock(
+            &app,
+            &manager,
+            Some(SessionLock::new(manager.clock.now(), remaining)),
+        )?;
+    }
+
     {
         let mut state = manager
             .state
             .lock()
-            .map_err(|_| "timer state mutex poisoned".to_string())?;
-        state.is_running = true;
+            .map_err(|_| AppError::poisoned("timer state"))?;
+        state.start(manager.clock.now());
     }
 
     let snapshot = {
         manager
             .state
             .lock()
-            .map_err(|_| "timer state mutex poisoned".to_string())?
+            .map_err(|_| AppError::poisoned("timer state"))?
             .clone()
     };
     emit_timer_state(&app, &snapshot);
 
     if let Some(blocker) = app.try_state::<FocusBlocker>() {
         if snapshot.phase == Phase::Work {
-            if let Err(e) = blocker.inner().enable() {
-                emit_blocking_error(
-                    &app,
-                    format!(
-                        "Focus-mode blocking couldn't be enabled (try running the app as Administrator): {e}"
-                    ),
-                );
+            if let Some(db) = app.try_state::<Database>() {
+                hold_blocking(&app, db.inner(), blocker.inner(), HoldSource::Timer);
             }
         } else {
-            let _ = blocker.inner().disable();
+            release_blocking(&app, blocker.inner(), HoldSource::Timer);
         }
     }
 
+    ensure_timer_worker(&app, manager.inner())
+}
+
+fn ensure_timer_worker(app: &AppHandle, manager: &TimerManager) -> Result<(), AppError> {
     if manager
         .worker_running
         .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
@@ -171,31 +525,110 @@ fn start_timer(app: AppHandle, manager: State<'_, TimerManager>) -> Result<(), S
     {
         let db = app
             .try_state::<Database>()
-            .ok_or_else(|| "database not initialized".to_string())?
+            .ok_or_else(|| AppError::not_found("database not initialized"))?
             .inner()
             .clone();
         let blocker = app
             .try_state::<FocusBlocker>()
-            .ok_or_else(|| "focus blocker not initialized".to_string())?
+            .ok_or_else(|| AppError::not_found("focus blocker not initialized"))?
             .inner()
             .clone();
-        spawn_timer_worker(app, manager.inner().clone(), db, blocker);
+        spawn_timer_worker(app.clone(), manager.clone(), db, blocker);
     }
 
     Ok(())
 }
 
+/// Picks a locked session back up after a restart, so quitting the app doesn't end it early.
+fn resume_locked_session(app: &AppHandle) -> Result<(), AppError> {
+    let db = app.state::<Database>();
+    let manager = app.state::<TimerManager>();
+    let Some(lock) = db.load_session_lock()? else {
+        return Ok(());
+    };
+    let now = manager.clock.now();
+    if lock.is_expired(now) {
+        return db.clear_session_lock();
+    }
+
+    {
+        let cfg = *manager
+            .cfg
+            .lock()
+            .map_err(|_| AppError::poisoned("timer cfg"))?;
+        let mut state = manager
+            .state
+            .lock()
+            .map_err(|_| AppError::poisoned("timer state"))?;
+        state.apply_phase(Phase::Work, cfg, now);
+        state.remaining_seconds = lock.remaining_seconds(now).min(state.total_seconds);
+        state.start(now);
+    }
+    *manager
+        .session_lock
+        .lock()
+        .map_err(|_| AppError::poisoned("session lock"))? = Some(lock);
+
+    hold_blocking(
+        app,
+        db.inner(),
+        app.state::<FocusBlocker>().inner(),
+        HoldSource::Timer,
+    );
+    ensure_timer_worker(app, manager.inner())
+}
+
+#[tauri::command]
+fn get_session_lock(
+    app: AppHandle,
+    manager: State<'_, TimerManager>,
+) -> Result<Option<SessionLockStatus>, AppError> {
+    Ok(current_session_lock(&app, &manager)?.map(|lock| lock.status()))
+}
+
+/// Starts the emergency-unlock cooldown.
+#[tauri::command]
+fn request_session_unlock(
+    app: AppHandle,
+    manager: State<'_, TimerManager>,
+) -> Result<SessionLockStatus, AppError> {
+    let mut lock = current_session_lock(&app, &manager)?
+        .ok_or_else(|| AppError::not_found("no locked session is running"))?;
+    lock.request_unlock(manager.clock.now());
+    let status = lock.status();
+    set_session_lock(&app, &manager, Some(lock))?;
+    Ok(status)
+}
+
+/// Lifts the lock with the unlock phrase, or without it once the cooldown has passed.
+/// The timer keeps running; pausing is allowed again afterwards.
+#[tauri::command]
+fn unlock_session(
+    app: AppHandle,
+    manager: State<'_, TimerManager>,
+    phrase: Option<String>,
+) -> Result<(), AppError> {
+    let Some(lock) = current_session_lock(&app, &manager)? else {
+        return Ok(());
+    };
+    lock.try_unlock(manager.clock.now(), phrase.as_deref())?;
+    set_session_lock(&app, &manager, None)
+}
+
 #[tauri::command]
-fn get_settings(db: State<'_, Database>) -> Result<Settings, String> {
+fn get_settings(db: State<'_, Database>) -> Result<Settings, AppError> {
     db.load_settings()
 }
 
-fn validate_settings(settings: &Settings) -> Result<(), String> {
-    fn in_range(v: u32, min: u32, max: u32, field: &str) -> Result<(), String> {
+fn validate_settings(settings: &Settings) -> Result<(), AppError> {
+    fn in_range(v: u32, min: u32, max: u32, field: &str) -> Result<(), AppError> {
         if (min..=max).contains(&v) {
             Ok(())
         } else {
-            Err(format!("{field} must be between {min} and {max}"))
+            Err(AppError::validation(
+                field,
+                format!("{field} must be between {min} and {max}"),
+            ))
         }
     }
 
@@ -204,6 +637,32 @@ fn validate_settings(settings: &Settings) -> Result<(), String> {
     in_range(settings.long_break_minutes, 1, 30, "longBreakMinutes")?;
     in_range(settings.long_break_after, 2, 10, "longBreakAfter")?;
     in_range(settings.daily_goal, 1, 20, "dailyGoal")?;
+    if let Some(path) = hosts_path_from_settings(settings) {
+        if !path.is_absolute() {
+            return Err(AppError::validation(
+                "hostsPath",
+                "hostsPath must be an absolute path",
+            ));
+        }
+    }
+    if settings.block_subdomains.len() > 20 {
+        return Err(AppError::validation(
+            "blockSubdomains",
+            "blockSubdomains can list at most 20 labels",
+        ));
+    }
+    for label in &settings.block_subdomains {
+        normalize_subdomain(label)?;
+    }
+    block_method_from_settings(settings)?;
+    if settings.block_mode == BlockMode::Allowlist
+        && settings.blocking_method == BlockingMethod::Hosts
+    {
+        return Err(AppError::validation(
+            "blockMode",
+            "blockMode allowlist needs the DNS or proxy blocking method",
+        ));
+    }
     Ok(())
 }
 
@@ -212,17 +671,33 @@ fn save_settings(
     app: AppHandle,
     db: State<'_, Database>,
     manager: State<'_, TimerManager>,
-    settings: Settings,
-) -> Result<Settings, String> {
+    mut settings: Settings,
+) -> Result<Settings, AppError> {
+    settings.block_subdomains = settings
+        .block_subdomains
+        .iter()
+        .map(|label| label.trim().to_ascii_lowercase())
+        .filter(|label| !label.is_empty())
+        .collect();
     validate_settings(&settings)?;
+    if changes_blocking(&db.load_settings()?, &settings) {
+        ensure_unlocked(&app, &manager, "change blocking settings")?;
+    }
+    if let Some(id) = settings.active_profile_id {
+        db.get_blocklist_profile(id)?;
+    }
     db.save_settings(&settings)?;
+    if let Some(blocker) = app.try_state::<FocusBlocker>() {
+        configure_blocker(&app, blocker.inner(), &settings)?;
+    }
+    refresh_blocking(&app, db.inner());
 
     let new_cfg = timer_config_from_settings(&settings);
     {
         let mut cfg = manager
             .cfg
             .lock()
-            .map_err(|_| "timer cfg mutex poisoned".to_string())?;
+            .map_err(|_| AppError::poisoned("timer cfg"))?;
         *cfg = new_cfg;
     }
 
@@ -230,14 +705,14 @@ fn save_settings(
         let mut state = manager
             .state
             .lock()
-            .map_err(|_| "timer state mutex poisoned".to_string())?;
+            .map_err(|_| AppError::poisoned("timer state"))?;
 
         state.long_break_after = new_cfg.long_break_after;
         state.daily_goal = new_cfg.daily_goal;
 
         if !state.is_running {
             let current_phase = state.phase;
-            state.apply_phase(current_phase, new_cfg);
+            state.apply_phase(current_phase, new_cfg, manager.clock.now());
         }
     }
 
@@ -248,22 +723,340 @@ fn save_settings(
 }
 
 #[tauri::command]
-fn get_today_statistics(db: State<'_, Database>) -> Result<TodayStatistics, String> {
+fn get_today_statistics(db: State<'_, Database>) -> Result<TodayStatistics, AppError> {
     db.get_today_statistics()
 }
 
+fn validate_profile_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > 40 {
+        return Err(AppError::validation(
+            "name",
+            "profile name must be between 1 and 40 characters",
+        ));
+    }
+    Ok(name.to_string())
+}
+
+/// Re-applies blocking when the active profile, its sites or the blocked apps change
+/// mid-session.
+fn refresh_blocking(app: &AppHandle, db: &Database) {
+    let Some(blocker) = app.try_state::<FocusBlocker>() else {
+        return;
+    };
+    if blocker.inner().is_enabled() {
+        enable_site_blocking(db, blocker.inner());
+    }
+    if let Some(processes) = app.try_state::<ProcessBlocker>() {
+        if processes.inner().is_enabled() {
+            enable_app_blocking(db, blocker.inner(), processes.inner());
+        }
+    }
+}
+
+#[tauri::command]
+fn get_blocklist_profiles(db: State<'_, Database>) -> Result<Vec<BlocklistProfile>, AppError> {
+    db.list_blocklist_profiles()
+}
+
+#[tauri::command]
+fn create_blocklist_profile(
+    db: State<'_, Database>,
+    name: String,
+    suppress_notifications: bool,
+) -> Result<BlocklistProfile, AppError> {
+    db.create_blocklist_profile(&validate_profile_name(&name)?, suppress_notifications)
+}
+
 #[tauri::command]
-fn pause_timer(app: AppHandle, manager: State<'_, TimerManager>) -> Result<TimerState, String> {
+fn update_blocklist_profile(
+    app: AppHandle,
+    db: State<'_, Database>,
+    manager: State<'_, TimerManager>,
+    id: i64,
+    name: String,
+    suppress_notifications: bool,
+) -> Result<BlocklistProfile, AppError> {
+    if db.get_blocklist_profile(id)?.suppress_notifications && !suppress_notifications {
+        ensure_unlocked(&app, &manager, "let notifications through")?;
+    }
+    let profile =
+        db.update_blocklist_profile(id, &validate_profile_name(&name)?, suppress_notifications)?;
+    refresh_blocking(&app, db.inner());
+    Ok(profile)
+}
+
+#[tauri::command]
+fn delete_blocklist_profile(
+    app: AppHandle,
+    db: State<'_, Database>,
+    manager: State<'_, TimerManager>,
+    id: i64,
+) -> Result<(), AppError> {
+    ensure_unlocked(&app, &manager, "delete a blocklist profile")?;
+    db.delete_blocklist_profile(id)?;
+    refresh_blocking(&app, db.inner());
+    Ok(())
+}
+
+#[tauri::command]
+fn set_active_profile(
+    app: AppHandle,
+    db: State<'_, Database>,
+    manager: State<'_, TimerManager>,
+    id: i64,
+) -> Result<Settings, AppError> {
+    ensure_unlocked(&app, &manager, "switch blocklist profiles")?;
+    db.get_blocklist_profile(id)?;
+    let mut settings = db.load_settings()?;
+    settings.active_profile_id = Some(id);
+    db.save_settings(&settings)?;
+    if let Some(blocker) = app.try_state::<FocusBlocker>() {
+        configure_blocker(&app, blocker.inner(), &settings)?;
+    }
+    refresh_blocking(&app, db.inner());
+    Ok(settings)
+}
+
+#[tauri::command]
+fn get_blocked_sites(
+    db: State<'_, Database>,
+    profile_id: i64,
+) -> Result<Vec<BlockedSite>, AppError> {
+    db.list_blocked_sites(profile_id)
+}
+
+/// Whether listing a site (or, with `listed` false, dropping one) lets more through: in
+/// allowlist mode the listed sites are the ones that stay reachable.
+fn site_change_loosens(mode: BlockMode, listed: bool) -> bool {
+    match mode {
+        BlockMode::Blocklist => !listed,
+        BlockMode::Allowlist => listed,
+    }
+}
+
+fn ensure_site_change_allowed(
+    app: &AppHandle,
+    db: &Database,
+    manager: &TimerManager,
+    listed: bool,
+) -> Result<(), AppError> {
+    let mode = db.load_settings()?.block_mode;
+    if !site_change_loosens(mode, listed) {
+        return Ok(());
+    }
+    let action = match mode {
+        BlockMode::Blocklist => "unblock a site",
+        BlockMode::Allowlist => "allow another site",
+    };
+    ensure_unlocked(app, manager, action)
+}
+
+#[tauri::command]
+fn add_blocked_site(
+    app: AppHandle,
+    db: State<'_, Database>,
+    manager: State<'_, TimerManager>,
+    profile_id: i64,
+    domain: String,
+) -> Result<BlockedSite, AppError> {
+    ensure_site_change_allowed(&app, &db, &manager, true)?;
+    db.get_blocklist_profile(profile_id)?;
+    let site = db.add_blocked_site(profile_id, &normalize_domain(&domain)?)?;
+    refresh_blocking(&app, db.inner());
+    Ok(site)
+}
+
+#[tauri::command]
+fn remove_blocked_site(
+    app: AppHandle,
+    db: State<'_, Database>,
+    manager: State<'_, TimerManager>,
+    id: i64,
+) -> Result<(), AppError> {
+    ensure_site_change_allowed(&app, &db, &manager, false)?;
+    db.remove_blocked_site(id)?;
+    refresh_blocking(&app, db.inner());
+    Ok(())
+}
+
+#[tauri::command]
+fn set_blocked_site_enabled(
+    app: AppHandle,
+    db: State<'_, Database>,
+    manager: State<'_, TimerManager>,
+    id: i64,
+    enabled: bool,
+) -> Result<(), AppError> {
+    ensure_site_change_allowed(&app, &db, &manager, enabled)?;
+    db.set_blocked_site_enabled(id, enabled)?;
+    refresh_blocking(&app, db.inner());
+    Ok(())
+}
+
+#[tauri::command]
+fn get_blocked_apps(db: State<'_, Database>) -> Result<Vec<BlockedApp>, AppError> {
+    db.list_blocked_apps()
+}
+
+#[tauri::command]
+fn add_blocked_app(
+    app: AppHandle,
+    db: State<'_, Database>,
+    name: String,
+) -> Result<BlockedApp, AppError> {
+    let blocked = db.add_blocked_app(&normalize_app_name(&name)?)?;
+    refresh_blocking(&app, db.inner());
+    Ok(blocked)
+}
+
+#[tauri::command]
+fn remove_blocked_app(
+    app: AppHandle,
+    db: State<'_, Database>,
+    manager: State<'_, TimerManager>,
+    id: i64,
+) -> Result<(), AppError> {
+    ensure_unlocked(&app, &manager, "unblock an app")?;
+    db.remove_blocked_app(id)?;
+    refresh_blocking(&app, db.inner());
+    Ok(())
+}
+
+#[tauri::command]
+fn set_blocked_app_enabled(
+    app: AppHandle,
+    db: State<'_, Database>,
+    manager: State<'_, TimerManager>,
+    id: i64,
+    enabled: bool,
+) -> Result<(), AppError> {
+    if !enabled {
+        ensure_unlocked(&app, &manager, "unblock an app")?;
+    }
+    db.set_blocked_app_enabled(id, enabled)?;
+    refresh_blocking(&app, db.inner());
+    Ok(())
+}
+
+#[tauri::command]
+fn get_block_schedules(db: State<'_, Database>) -> Result<Vec<BlockSchedule>, AppError> {
+    db.list_block_schedules()
+}
+
+#[tauri::command]
+fn add_block_schedule(
+    app: AppHandle,
+    db: State<'_, Database>,
+    days: Vec<u8>,
+    start: String,
+    end: String,
+) -> Result<BlockSchedule, AppError> {
+    let (days, start, end) = normalize_schedule(&days, &start, &end)?;
+    let schedule = db.add_block_schedule(&days, &start, &end)?;
+    apply_schedules(&app);
+    Ok(schedule)
+}
+
+#[tauri::command]
+fn remove_block_schedule(app: AppHandle, db: State<'_, Database>, id: i64) -> Result<(), AppError> {
+    db.remove_block_schedule(id)?;
+    apply_schedules(&app);
+    Ok(())
+}
+
+#[tauri::command]
+fn set_block_schedule_enabled(
+    app: AppHandle,
+    db: State<'_, Database>,
+    id: i64,
+    enabled: bool,
+) -> Result<(), AppError> {
+    db.set_block_schedule_enabled(id, enabled)?;
+    apply_schedules(&app);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_blocker_status(blocker: State<'_, FocusBlocker>) -> Result<BlockerStatus, AppError> {
+    blocker.status()
+}
+
+#[tauri::command]
+fn get_hosts_recovery(blocker: State<'_, FocusBlocker>) -> Option<HostsRecovery> {
+    blocker.hosts_recovery()
+}
+
+/// Abandons the current phase and goes to the next one in the cycle. The abandoned phase is
+/// recorded as incomplete.
+#[tauri::command]
+fn skip_phase(app: AppHandle, manager: State<'_, TimerManager>) -> Result<TimerState, AppError> {
+    change_phase(&app, &manager, None)
+}
+
+/// Abandons the current phase and jumps to `phase`, e.g. straight into a long break.
+#[tauri::command]
+fn set_phase(
+    app: AppHandle,
+    manager: State<'_, TimerManager>,
+    phase: Phase,
+) -> Result<TimerState, AppError> {
+    change_phase(&app, &manager, Some(phase))
+}
+
+fn change_phase(
+    app: &AppHandle,
+    manager: &TimerManager,
+    to: Option<Phase>,
+) -> Result<TimerState, AppError> {
+    ensure_unlocked(app, manager, "leave the work phase")?;
+    let (evt, snapshot) = {
+        let cfg = *manager
+            .cfg
+            .lock()
+            .map_err(|_| AppError::poisoned("timer cfg"))?;
+        let mut state = manager
+            .state
+            .lock()
+            .map_err(|_| AppError::poisoned("timer state"))?;
+        let to = to.unwrap_or_else(|| state.skip_target());
+        let evt = state.skip_to(to, cfg, manager.clock.now());
+        (evt, state.clone())
+    };
+
+    if let Some(blocker) = app.try_state::<FocusBlocker>() {
+        match app.try_state::<Database>() {
+            Some(db) if snapshot.is_running && snapshot.phase == Phase::Work => {
+                hold_blocking(app, db.inner(), blocker.inner(), HoldSource::Timer);
+            }
+            _ => release_blocking(app, blocker.inner(), HoldSource::Timer),
+        }
+    }
+    record_interrupted_phase(app, (evt.from, evt.completed_seconds), evt.ended_at)?;
+
+    emit_phase_complete(app, &evt);
+    emit_timer_state(app, &snapshot);
+    Ok(snapshot)
+}
+
+#[tauri::command]
+fn restore_hosts_backup(blocker: State<'_, FocusBlocker>) -> Result<(), AppError> {
+    blocker.restore_hosts_backup()
+}
+
+#[tauri::command]
+fn pause_timer(app: AppHandle, manager: State<'_, TimerManager>) -> Result<TimerState, AppError> {
+    ensure_unlocked(&app, &manager, "pause the timer")?;
     {
         let mut state = manager
             .state
             .lock()
-            .map_err(|_| "timer state mutex poisoned".to_string())?;
-        state.is_running = false;
+            .map_err(|_| AppError::poisoned("timer state"))?;
+        state.pause(manager.clock.now());
     }
 
     if let Some(blocker) = app.try_state::<FocusBlocker>() {
-        let _ = blocker.inner().disable();
+        release_blocking(&app, blocker.inner(), HoldSource::Timer);
     }
 
     let snapshot = get_timer_state(manager)?;
@@ -271,18 +1064,54 @@ fn pause_timer(app: AppHandle, manager: State<'_, TimerManager>) -> Result<Timer
     Ok(snapshot)
 }
 
+/// Records a phase that was abandoned part-way through. Nothing is recorded if it never
+/// actually ran.
+fn record_interrupted_phase(
+    app: &AppHandle,
+    (phase, elapsed_seconds): (Phase, u32),
+    ended_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let Some(db) = app.try_state::<Database>() else {
+        return Ok(());
+    };
+    if store_interrupted_phase(db.inner(), phase, elapsed_seconds, ended_at)? {
+        if let Ok(stats) = db.get_today_statistics() {
+            emit_today_statistics(app, &stats);
+        }
+    }
+    Ok(())
+}
+
+/// Database half of [`record_interrupted_phase`]; returns whether anything was written.
+fn store_interrupted_phase(
+    db: &Database,
+    phase: Phase,
+    elapsed_seconds: u32,
+    ended_at: DateTime<Utc>,
+) -> Result<bool, AppError> {
+    if elapsed_seconds == 0 {
+        return Ok(false);
+    }
+    db.insert_incomplete_phase(phase, elapsed_seconds, ended_at)?;
+    Ok(true)
+}
+
 #[tauri::command]
-fn reset_timer(app: AppHandle, manager: State<'_, TimerManager>) -> Result<TimerState, String> {
-    {
+fn reset_timer(app: AppHandle, manager: State<'_, TimerManager>) -> Result<TimerState, AppError> {
+    ensure_unlocked(&app, &manager, "reset the timer")?;
+    let interrupted = {
         let mut state = manager
             .state
             .lock()
-            .map_err(|_| "timer state mutex poisoned".to_string())?;
+            .map_err(|_| AppError::poisoned("timer state"))?;
+        let interrupted = (state.phase, state.elapsed_seconds(manager.clock.now()));
         state.reset_current_phase();
-    }
+        interrupted
+    };
+    record_interrupted_phase(&app, interrupted, manager.clock.now())?;
 
     if let Some(blocker) = app.try_state::<FocusBlocker>() {
-        let _ = blocker.inner().disable();
+        release_blocking(&app, blocker.inner(), HoldSource::Timer);
     }
 
     let snapshot = get_timer_state(manager)?;
@@ -290,24 +1119,81 @@ fn reset_timer(app: AppHandle, manager: State<'_, TimerManager>) -> Result<Timer
     Ok(snapshot)
 }
 
+/// Entry point of the `focus-hosts-helper` binary.
+pub fn run_hosts_helper() -> Result<(), AppError> {
+    hosts_helper::run()
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .setup(|app| {
-            let db = Database::new(&app.handle())?;
+            let clock = clock::system_clock();
+            let db = Database::new(app.handle(), clock.clone())?;
             let settings = db.load_settings().unwrap_or_default();
             let cfg = timer_config_from_settings(&settings);
+            let manager = TimerManager::new(cfg, clock);
+            let journal = match app.path().app_data_dir() {
+                Ok(dir) => Journal::open(dir.join("focus-journal.json")),
+                Err(_) => Journal::default(),
+            };
             let blocker = FocusBlocker::default();
+            // A DNS/proxy port that's taken shouldn't keep the app from starting; it shows up
+            // in the blocker status instead.
+            if let Err(e) = configure_blocker(app.handle(), &blocker, &settings) {
+                blocker.report_error(e);
+            }
+            blocker.set_time_left(time_left_from_timer(&manager));
+            blocker.set_journal(journal.clone());
             blocker.ensure_disabled();
+            let handle = app.handle().clone();
+            blocker
+                .set_status_listener(Arc::new(move |status| emit_blocker_status(&handle, status)));
+            let processes = ProcessBlocker::default();
+            processes.set_journal(journal);
             app.manage(db);
-            app.manage(TimerManager::new(cfg));
+            app.manage(manager);
             app.manage(blocker);
+            app.manage(processes);
+            app.manage(BlockingHolds::default());
+            if let Err(e) = resume_locked_session(app.handle()) {
+                app.state::<FocusBlocker>().report_error(e);
+            }
+            spawn_scheduler(app.handle().clone());
             Ok(())
         })
         .on_window_event(|window, event| {
-            if matches!(event, WindowEvent::CloseRequested { .. }) {
-                if let Some(blocker) = window.app_handle().try_state::<FocusBlocker>() {
-                    let _ = blocker.inner().disable();
+            if let WindowEvent::CloseRequested { api, .. } = event {
+                let app = window.app_handle();
+                // A locked session can't be quit; if the app dies anyway, the next start
+                // resumes it.
+                let locked = app
+                    .try_state::<TimerManager>()
+                    .and_then(|manager| current_session_lock(app, manager.inner()).ok())
+                    .flatten()
+                    .is_some();
+                if locked {
+                    api.prevent_close();
+                    return;
+                }
+                if let Some(manager) = app.try_state::<TimerManager>() {
+                    let interrupted = manager
+                        .state
+                        .lock()
+                        .map(|state| (state.phase, state.elapsed_seconds(manager.clock.now())));
+                    if let Ok(interrupted) = interrupted {
+                        let _ = record_interrupted_phase(app, interrupted, manager.clock.now());
+                    }
+                }
+                if let Some(blocker) = app.try_state::<FocusBlocker>() {
+                    // Schedules can't be enforced once the app is gone either.
+                    let disable = || disable_blocking(app, blocker.inner());
+                    match app.try_state::<BlockingHolds>() {
+                        Some(holds) => {
+                            let _ = holds.release_all(disable);
+                        }
+                        None => disable(),
+                    }
                 }
             }
         })
@@ -319,10 +1205,113 @@ pub fn run() {
             start_timer,
             pause_timer,
             reset_timer,
+            skip_phase,
+            set_phase,
             get_settings,
             save_settings,
-            get_today_statistics
+            get_today_statistics,
+            get_blocklist_profiles,
+            create_blocklist_profile,
+            update_blocklist_profile,
+            delete_blocklist_profile,
+            set_active_profile,
+            get_blocked_sites,
+            add_blocked_site,
+            remove_blocked_site,
+            set_blocked_site_enabled,
+            get_blocked_apps,
+            add_blocked_app,
+            remove_blocked_app,
+            set_blocked_app_enabled,
+            get_block_schedules,
+            add_block_schedule,
+            remove_block_schedule,
+            set_block_schedule_enabled,
+            get_session_lock,
+            request_session_unlock,
+            unlock_session,
+            get_blocker_status,
+            get_hosts_recovery,
+            restore_hosts_backup
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clock::FakeClock;
+    use std::cell::Cell;
+
+    #[test]
+    fn only_site_changes_that_let_more_through_need_an_unlock() {
+        assert!(site_change_loosens(BlockMode::Blocklist, false));
+        assert!(!site_change_loosens(BlockMode::Blocklist, true));
+        assert!(site_change_loosens(BlockMode::Allowlist, true));
+        assert!(!site_change_loosens(BlockMode::Allowlist, false));
+    }
+
+    #[test]
+    fn schedule_and_timer_holds_overlap() {
+        // 2024-01-01 is a Monday; the window is 09:00-10:00 on Mondays.
+        let clock = FakeClock::at("2024-01-01T08:50:00Z");
+        let db = Database::in_memory(clock.clone()).unwrap();
+        db.add_block_schedule(&[0], "09:00", "10:00").unwrap();
+        let holds = BlockingHolds::default();
+        let (enabled, disabled) = (Cell::new(0), Cell::new(0));
+        let sync = || {
+            sync_schedule_hold(
+                &db,
+                &holds,
+                clock.as_ref(),
+                || enabled.set(enabled.get() + 1),
+                || disabled.set(disabled.get() + 1),
+            )
+            .unwrap()
+        };
+
+        // A work phase starts before the window opens.
+        holds.hold(HoldSource::Timer, || {}).unwrap();
+        sync();
+        assert!(!holds.is_held_by(HoldSource::Schedule));
+
+        clock.advance_secs(15 * 60);
+        sync();
+        assert!(holds.is_held_by(HoldSource::Schedule));
+        assert_eq!(enabled.get(), 1);
+
+        // The break inside the window doesn't lift the block.
+        holds
+            .release(HoldSource::Timer, || disabled.set(disabled.get() + 1))
+            .unwrap();
+        assert_eq!(disabled.get(), 0);
+
+        // The next work phase outlasts the window, so its end doesn't either.
+        holds.hold(HoldSource::Timer, || {}).unwrap();
+        clock.advance_secs(60 * 60);
+        sync();
+        assert!(!holds.is_held_by(HoldSource::Schedule));
+        assert_eq!(disabled.get(), 0);
+
+        // Once the timer lets go too, blocking goes off.
+        holds
+            .release(HoldSource::Timer, || disabled.set(disabled.get() + 1))
+            .unwrap();
+        assert_eq!(disabled.get(), 1);
+        sync();
+        assert_eq!((enabled.get(), disabled.get()), (1, 1));
+    }
+
+    #[test]
+    fn skipping_a_phase_that_never_ran_is_not_an_interruption() {
+        let clock = FakeClock::at("2024-01-01T10:00:00Z");
+        let db = Database::in_memory(clock.clone()).unwrap();
+
+        assert!(!store_interrupted_phase(&db, Phase::Work, 0, clock.now()).unwrap());
+        assert_eq!(db.get_today_statistics().unwrap().interruptions, 0);
+
+        assert!(store_interrupted_phase(&db, Phase::Work, 90, clock.now()).unwrap());
+        assert_eq!(db.get_today_statistics().unwrap().interruptions, 1);
+    }
+}
@@ -0,0 +1,458 @@
+use rusqlite::{params, Connection, Transaction};
+
+use crate::error::AppError;
+use crate::focus_blocker::DEFAULT_BLOCKED_DOMAINS;
+
+/// One schema change. It is applied in a transaction together with the `user_version` bump,
+/// so a failure leaves the database at the previous version.
+struct Migration {
+    version: i64,
+    description: &'static str,
+    apply: fn(&Transaction<'_>) -> Result<(), AppError>,
+}
+
+/// Applied in order to anything below their version. Append new steps; never edit or
+/// reorder ones that have shipped.
+///
+/// Steps 1–3 replay changes made before the schema was versioned. Databases from that time
+/// are at version 0 with any subset of them already applied, so those steps check the
+/// schema before altering it.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "timer settings, sessions, blocklist profiles and blocked apps",
+        apply: core_tables,
+    },
+    Migration {
+        version: 2,
+        description: "blocking settings",
+        apply: blocking_settings,
+    },
+    Migration {
+        version: 3,
+        description: "session lock and blocking schedules",
+        apply: lock_and_schedules,
+    },
+    Migration {
+        version: 4,
+        description: "session durations in seconds",
+        apply: duration_seconds,
+    },
+    Migration {
+        version: 5,
+        description: "short and long breaks in sessions",
+        apply: break_types,
+    },
+];
+
+#[cfg(test)]
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+/// Brings the database up to the latest version.
+pub fn run(conn: &mut Connection) -> Result<(), AppError> {
+    run_steps(conn, MIGRATIONS)
+}
+
+fn run_steps(conn: &mut Connection, steps: &[Migration]) -> Result<(), AppError> {
+    let current = user_version(conn)?;
+    for step in steps.iter().filter(|step| step.version > current) {
+        let context = format!("migration {} ({})", step.version, step.description);
+        let tx = conn
+            .transaction()
+            .map_err(|e| AppError::database(&context, e))?;
+        (step.apply)(&tx).map_err(|e| e.context(&context))?;
+        tx.pragma_update(None, "user_version", step.version)
+            .and_then(|_| tx.commit())
+            .map_err(|e| AppError::database(&context, e))?;
+    }
+    Ok(())
+}
+
+fn user_version(conn: &Connection) -> Result<i64, AppError> {
+    conn.pragma_query_value(None, "user_version", |r| r.get(0))
+        .map_err(|e| AppError::database("failed to read schema version", e))
+}
+
+fn core_tables(tx: &Transaction<'_>) -> Result<(), AppError> {
+    let had_blocked_sites = table_exists(tx, "blocked_sites")?;
+    let had_profiles = table_exists(tx, "blocklist_profiles")?;
+
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            work_minutes INTEGER NOT NULL,
+            short_break_minutes INTEGER NOT NULL,
+            long_break_minutes INTEGER NOT NULL,
+            long_break_after INTEGER NOT NULL,
+            daily_goal INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS sessions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            date TEXT NOT NULL,
+            start_time TEXT NOT NULL,
+            end_time TEXT NOT NULL,
+            duration_minutes INTEGER NOT NULL,
+            phase_type TEXT NOT NULL,
+            completed INTEGER NOT NULL DEFAULT 1,
+            notes TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS blocklist_profiles (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            suppress_notifications INTEGER NOT NULL DEFAULT 1
+        );
+
+        CREATE TABLE IF NOT EXISTS blocked_apps (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            enabled INTEGER NOT NULL DEFAULT 1
+        );",
+    )
+    .map_err(|e| AppError::database("failed to create tables", e))?;
+
+    if !had_profiles {
+        tx.execute(
+            "INSERT INTO blocklist_profiles (name, suppress_notifications) VALUES (?1, 1)",
+            params![DEFAULT_PROFILE_NAME],
+        )
+        .map_err(|e| AppError::database("failed to seed blocklist profiles", e))?;
+    }
+    let default_profile_id: i64 = tx
+        .query_row("SELECT MIN(id) FROM blocklist_profiles", [], |r| r.get(0))
+        .map_err(|e| AppError::database("failed to read blocklist profiles", e))?;
+
+    if had_blocked_sites && !column_exists(tx, "blocked_sites", "profile_id")? {
+        // Sites used to be one global list; move them into the default profile. The table is
+        // rebuilt because the uniqueness constraint changes from `domain` to `(profile_id, domain)`.
+        tx.execute_batch("ALTER TABLE blocked_sites RENAME TO blocked_sites_old;")
+            .and_then(|_| tx.execute_batch(CREATE_BLOCKED_SITES))
+            .and_then(|_| {
+                tx.execute(
+                    "INSERT INTO blocked_sites (profile_id, domain, enabled)
+                     SELECT ?1, domain, enabled FROM blocked_sites_old",
+                    params![default_profile_id],
+                )
+            })
+            .and_then(|_| tx.execute_batch("DROP TABLE blocked_sites_old;"))
+            .map_err(|e| AppError::database("failed to migrate blocked sites", e))?;
+    } else {
+        tx.execute_batch(CREATE_BLOCKED_SITES)
+            .map_err(|e| AppError::database("failed to create blocked sites", e))?;
+    }
+
+    // Seed the blocklist only when the table is first created, so users can remove defaults.
+    if !had_blocked_sites {
+        for domain in DEFAULT_BLOCKED_DOMAINS {
+            tx.execute(
+                "INSERT OR IGNORE INTO blocked_sites (profile_id, domain, enabled)
+                 VALUES (?1, ?2, 1)",
+                params![default_profile_id, domain],
+            )
+            .map_err(|e| AppError::database("failed to seed blocked sites", e))?;
+        }
+    }
+
+    // `www.` variants are generated by subdomain expansion now, so store bare domains only.
+    tx.execute_batch(
+        "UPDATE OR IGNORE blocked_sites SET domain = substr(domain, 5) WHERE domain LIKE 'www.%';
+         DELETE FROM blocked_sites WHERE domain LIKE 'www.%';",
+    )
+    .map_err(|e| AppError::database("failed to migrate blocked sites", e))
+}
+
+fn blocking_settings(tx: &Transaction<'_>) -> Result<(), AppError> {
+    let columns = [
+        ("active_profile_id", "INTEGER"),
+        ("block_subdomains", "TEXT"),
+        ("hosts_path", "TEXT"),
+        ("use_hosts_helper", "INTEGER NOT NULL DEFAULT 0"),
+        ("blocking_method", "TEXT NOT NULL DEFAULT 'hosts'"),
+        ("dns_listen_addr", "TEXT"),
+        ("dns_upstream", "TEXT"),
+        ("block_mode", "TEXT NOT NULL DEFAULT 'blocklist'"),
+        ("proxy_listen_addr", "TEXT"),
+        ("app_block_action", "TEXT NOT NULL DEFAULT 'terminate'"),
+    ];
+    for (column, definition) in columns {
+        if !column_exists(tx, "settings", column)? {
+            tx.execute_batch(&format!(
+                "ALTER TABLE settings ADD COLUMN {column} {definition};"
+            ))
+            .map_err(|e| AppError::database(&format!("failed to add settings.{column}"), e))?;
+        }
+    }
+    Ok(())
+}
+
+fn lock_and_schedules(tx: &Transaction<'_>) -> Result<(), AppError> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS session_lock (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            ends_at TEXT NOT NULL,
+            unlock_requested_at TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS block_schedules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            days TEXT NOT NULL,
+            start_time TEXT NOT NULL,
+            end_time TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1
+        );",
+    )
+    .map_err(|e| AppError::database("failed to create tables", e))
+}
+
+/// Sessions store exact seconds instead of whole minutes. Old rows get theirs back from the
+/// start/end timestamps, which were always written exactly.
+fn duration_seconds(tx: &Transaction<'_>) -> Result<(), AppError> {
+    if !column_exists(tx, "sessions", "duration_minutes")? {
+        return Ok(());
+    }
+    tx.execute_batch(
+        "ALTER TABLE sessions RENAME TO sessions_old;
+
+        CREATE TABLE sessions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            date TEXT NOT NULL,
+            start_time TEXT NOT NULL,
+            end_time TEXT NOT NULL,
+            duration_seconds INTEGER NOT NULL,
+            phase_type TEXT NOT NULL,
+            completed INTEGER NOT NULL DEFAULT 1,
+            notes TEXT
+        );
+
+        INSERT INTO sessions (id, date, start_time, end_time,
+            duration_seconds, phase_type, completed, notes)
+        SELECT id, date, start_time, end_time,
+            COALESCE(
+                MAX(0, CAST(ROUND((julianday(end_time) - julianday(start_time)) * 86400)
+                    AS INTEGER)),
+                duration_minutes * 60
+            ),
+            phase_type, completed, notes
+        FROM sessions_old;
+
+        DROP TABLE sessions_old;",
+    )
+    .map_err(|e| AppError::database("failed to convert session durations", e))
+}
+
+/// `phase_type` used to be `'break'` for both lengths. Which one an old row was isn't stored
+/// anywhere, so it is judged by duration: at least halfway between the configured short and
+/// long break counts as long.
+fn break_types(tx: &Transaction<'_>) -> Result<(), AppError> {
+    tx.execute(
+        "UPDATE sessions
+         SET phase_type = CASE
+             WHEN duration_seconds * 2 >= COALESCE(
+                 (SELECT short_break_minutes + long_break_minutes FROM settings WHERE id = 1),
+                 20
+             ) * 60 THEN 'long_break'
+             ELSE 'short_break'
+         END
+         WHERE phase_type = 'break'",
+        [],
+    )
+    .map_err(|e| AppError::database("failed to split break sessions", e))?;
+    Ok(())
+}
+
+const DEFAULT_PROFILE_NAME: &str = "Default";
+
+const CREATE_BLOCKED_SITES: &str = "CREATE TABLE IF NOT EXISTS blocked_sites (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    profile_id INTEGER NOT NULL REFERENCES blocklist_profiles(id),
+    domain TEXT NOT NULL,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    UNIQUE (profile_id, domain)
+);";
+
+fn table_exists(conn: &Connection, table: &str) -> Result<bool, AppError> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        params![table],
+        |r| Ok(r.get::<_, i64>(0)? > 0),
+    )
+    .map_err(|e| AppError::database("failed to inspect database schema", e))
+}
+
+fn column_exists(conn: &Connection, table: &str, column: &str) -> Result<bool, AppError> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+        params![table, column],
+        |r| Ok(r.get::<_, i64>(0)? > 0),
+    )
+    .map_err(|e| AppError::database("failed to inspect database schema", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A database as the first release left it, plus the global site list that came soon
+    /// after, before any versioning.
+    const V0_FIXTURE: &str = "
+        CREATE TABLE settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            work_minutes INTEGER NOT NULL,
+            short_break_minutes INTEGER NOT NULL,
+            long_break_minutes INTEGER NOT NULL,
+            long_break_after INTEGER NOT NULL,
+            daily_goal INTEGER NOT NULL
+        );
+        CREATE TABLE sessions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            date TEXT NOT NULL,
+            start_time TEXT NOT NULL,
+            end_time TEXT NOT NULL,
+            duration_minutes INTEGER NOT NULL,
+            phase_type TEXT NOT NULL,
+            completed INTEGER NOT NULL DEFAULT 1,
+            notes TEXT
+        );
+        CREATE TABLE blocked_sites (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            domain TEXT NOT NULL UNIQUE,
+            enabled INTEGER NOT NULL DEFAULT 1
+        );
+
+        INSERT INTO settings VALUES (1, 50, 10, 20, 3, 6);
+        INSERT INTO sessions (date, start_time, end_time, duration_minutes, phase_type)
+        VALUES ('2024-01-01', '2024-01-01T09:00:00+00:00', '2024-01-01T09:50:00+00:00',
+                50, 'work'),
+               ('2024-01-01', '2024-01-01T09:50:00+00:00', '2024-01-01T10:00:00+00:00',
+                10, 'break'),
+               ('2024-01-01', '2024-01-01T12:00:00+00:00', '2024-01-01T12:20:00+00:00',
+                20, 'break');
+        INSERT INTO blocked_sites (domain, enabled)
+        VALUES ('www.example.com', 1), ('news.example.org', 0);
+    ";
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |r| r.get(0)).unwrap()
+    }
+
+    #[test]
+    fn fresh_database_gets_the_latest_schema() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run(&mut conn).unwrap();
+
+        assert_eq!(user_version(&conn).unwrap(), latest_version());
+        assert!(column_exists(&conn, "sessions", "duration_seconds").unwrap());
+        assert!(column_exists(&conn, "settings", "app_block_action").unwrap());
+        assert!(table_exists(&conn, "block_schedules").unwrap());
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM blocked_sites") as usize,
+            DEFAULT_BLOCKED_DOMAINS.len()
+        );
+    }
+
+    #[test]
+    fn upgrades_a_v0_database_and_keeps_its_data() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(V0_FIXTURE).unwrap();
+        assert_eq!(user_version(&conn).unwrap(), 0);
+
+        run(&mut conn).unwrap();
+        assert_eq!(user_version(&conn).unwrap(), latest_version());
+
+        let (work_minutes, blocking_method): (i64, String) = conn
+            .query_row(
+                "SELECT work_minutes, blocking_method FROM settings WHERE id = 1",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((work_minutes, blocking_method.as_str()), (50, "hosts"));
+
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT duration_seconds FROM sessions WHERE phase_type = 'work'"
+            ),
+            50 * 60
+        );
+        // Short and long breaks are 10 and 20 minutes here, so 15 splits old breaks.
+        let breaks: Vec<(i64, String)> = conn
+            .prepare(
+                "SELECT duration_seconds, phase_type FROM sessions
+                 WHERE phase_type != 'work' ORDER BY start_time",
+            )
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            breaks,
+            vec![
+                (10 * 60, "short_break".to_string()),
+                (20 * 60, "long_break".to_string()),
+            ]
+        );
+
+        // The user's own list moves into the default profile, `www.` stripped, unseeded.
+        let sites: Vec<(String, bool)> = conn
+            .prepare("SELECT domain, enabled FROM blocked_sites ORDER BY domain")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get::<_, i64>(1)? != 0)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            sites,
+            vec![
+                ("example.com".to_string(), true),
+                ("news.example.org".to_string(), false),
+            ]
+        );
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(DISTINCT profile_id) FROM blocked_sites"
+            ),
+            1
+        );
+
+        // Running again changes nothing.
+        run(&mut conn).unwrap();
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM blocked_sites"), 2);
+    }
+
+    #[test]
+    fn failed_step_is_rolled_back() {
+        fn create_a(tx: &Transaction<'_>) -> Result<(), AppError> {
+            tx.execute_batch("CREATE TABLE a (x INTEGER);")
+                .map_err(|e| AppError::database("a", e))
+        }
+        fn create_b_then_fail(tx: &Transaction<'_>) -> Result<(), AppError> {
+            tx.execute_batch("CREATE TABLE b (x INTEGER); INSERT INTO missing VALUES (1);")
+                .map_err(|e| AppError::database("b", e))
+        }
+        let steps = [
+            Migration {
+                version: 1,
+                description: "a",
+                apply: create_a,
+            },
+            Migration {
+                version: 2,
+                description: "b",
+                apply: create_b_then_fail,
+            },
+        ];
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        let err = run_steps(&mut conn, &steps).unwrap_err();
+        assert!(err.message().starts_with("migration 2 (b)"));
+        assert_eq!(user_version(&conn).unwrap(), 1);
+        assert!(table_exists(&conn, "a").unwrap());
+        assert!(!table_exists(&conn, "b").unwrap());
+    }
+}
@@ -1,13 +1,35 @@
 use serde::{Deserialize, Serialize};
 
+use crate::focus_blocker::DEFAULT_SUBDOMAINS;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
 pub struct Settings {
     pub work_minutes: u32,
     pub short_break_minutes: u32,
     pub long_break_minutes: u32,
     pub long_break_after: u32,
     pub daily_goal: u32,
+    /// Blocklist profile used for work sessions; `None` falls back to the first profile.
+    pub active_profile_id: Option<i64>,
+    /// Subdomain labels expanded in front of every blocked domain (`www`, `m`, ...).
+    pub block_subdomains: Vec<String>,
+    /// Hosts file to edit instead of the platform default (`/etc/hosts`, or the Windows one).
+    pub hosts_path: Option<String>,
+    /// Send hosts file edits to the elevated `focus-hosts-helper` instead of writing directly.
+    pub use_hosts_helper: bool,
+    /// How sites are blocked during work sessions.
+    pub blocking_method: BlockingMethod,
+    /// Whether the active profile lists sites to block or the only sites to allow.
+    pub block_mode: BlockMode,
+    /// Where the DNS sinkhole listens; the system resolver must point at this address.
+    pub dns_listen_addr: String,
+    /// Resolver that non-blocked DNS queries are forwarded to.
+    pub dns_upstream: String,
+    /// Where the blocking proxy listens; browsers must use it as their HTTP/HTTPS proxy.
+    pub proxy_listen_addr: String,
+    /// What happens to blocked applications that are running during a work session.
+    pub app_block_action: AppBlockAction,
 }
 
 impl Default for Settings {
@@ -18,6 +40,99 @@ impl Default for Settings {
             long_break_minutes: 15,
             long_break_after: 4,
             daily_goal: 8,
+            active_profile_id: None,
+            block_subdomains: DEFAULT_SUBDOMAINS.iter().map(|s| s.to_string()).collect(),
+            hosts_path: None,
+            use_hosts_helper: false,
+            blocking_method: BlockingMethod::Hosts,
+            block_mode: BlockMode::Blocklist,
+            dns_listen_addr: "127.0.0.1:53".to_string(),
+            dns_upstream: "1.1.1.1:53".to_string(),
+            proxy_listen_addr: "127.0.0.1:8899".to_string(),
+            app_block_action: AppBlockAction::Terminate,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockingMethod {
+    /// Sinkhole blocked domains in the system hosts file.
+    #[default]
+    Hosts,
+    /// Answer blocked domains from a local DNS resolver.
+    Dns,
+    /// Refuse blocked domains in a local HTTP/HTTPS proxy.
+    Proxy,
+}
+
+impl BlockingMethod {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BlockingMethod::Hosts => "hosts",
+            BlockingMethod::Dns => "dns",
+            BlockingMethod::Proxy => "proxy",
+        }
+    }
+
+    /// Unknown values (e.g. from a newer version) fall back to the hosts file.
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "dns" => BlockingMethod::Dns,
+            "proxy" => BlockingMethod::Proxy,
+            _ => BlockingMethod::Hosts,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockMode {
+    /// Block the profile's sites.
+    #[default]
+    Blocklist,
+    /// Block everything except the profile's sites (DNS or proxy method only).
+    Allowlist,
+}
+
+impl BlockMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BlockMode::Blocklist => "blocklist",
+            BlockMode::Allowlist => "allowlist",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "allowlist" => BlockMode::Allowlist,
+            _ => BlockMode::Blocklist,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AppBlockAction {
+    /// Ask the application to quit (SIGTERM).
+    #[default]
+    Terminate,
+    /// Freeze the application until the work session ends (SIGSTOP/SIGCONT).
+    Suspend,
+}
+
+impl AppBlockAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AppBlockAction::Terminate => "terminate",
+            AppBlockAction::Suspend => "suspend",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "suspend" => AppBlockAction::Suspend,
+            _ => AppBlockAction::Terminate,
         }
     }
 }
@@ -28,8 +143,55 @@ pub struct TodayStatistics {
     pub date: String,
     pub total_focus_minutes: u32,
     pub work_sessions_completed: u32,
+    /// Completed breaks of either length; the sum of the two below.
     pub breaks_taken: u32,
+    pub short_breaks_taken: u32,
+    pub long_breaks_taken: u32,
+    /// Work phases cut short today by a reset, a skip or closing the app.
+    pub interruptions: u32,
+    /// Share of today's work phases that ran to the end, 0.0–1.0; `None` before the first.
+    pub completion_rate: Option<f64>,
     pub current_streak: u32,
     pub longest_streak: u32,
     pub daily_goal: u32,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockedSite {
+    pub id: i64,
+    pub profile_id: i64,
+    pub domain: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlocklistProfile {
+    pub id: i64,
+    pub name: String,
+    pub suppress_notifications: bool,
+    pub site_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockedApp {
+    pub id: i64,
+    /// Executable name, lowercased and without `.exe`.
+    pub name: String,
+    pub enabled: bool,
+}
+
+/// Recurring window during which sites are blocked whether or not the timer runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockSchedule {
+    pub id: i64,
+    /// Days the window starts on, 0 = Monday ... 6 = Sunday.
+    pub days: Vec<u8>,
+    /// Local "HH:MM". An `end` before `start` runs past midnight.
+    pub start: String,
+    pub end: String,
+    pub enabled: bool,
+}
@@ -0,0 +1,559 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::focus_blocker::Journal;
+use crate::models::AppBlockAction;
+
+const SCAN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Keeps configured applications (Discord, Steam, ...) closed or frozen during work
+/// sessions by watching the process list.
+#[derive(Clone, Default)]
+pub struct ProcessBlocker {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    targets: Vec<String>,
+    action: AppBlockAction,
+    /// Processes we stopped; resumed by `disable`.
+    suspended: Vec<SuspendedProcess>,
+    stop: Option<Arc<AtomicBool>>,
+    watcher: Option<JoinHandle<()>>,
+    /// Frozen processes are recorded here so a restart can resume them after a crash.
+    journal: Journal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SuspendedProcess {
+    pub pid: u32,
+    pub name: String,
+}
+
+/// Lowercased executable name without a Windows `.exe` suffix, so `Discord.exe` and
+/// `discord` refer to the same application.
+pub fn normalize_app_name(input: &str) -> Result<String, AppError> {
+    let name = input.trim();
+    let name = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    let name = name.to_ascii_lowercase();
+    let name = name.strip_suffix(".exe").unwrap_or(&name).to_string();
+    if name.is_empty() || name.len() > 255 || name.chars().any(char::is_control) {
+        return Err(AppError::validation(
+            "name",
+            format!("\"{}\" is not a valid application name", input.trim()),
+        ));
+    }
+    Ok(name)
+}
+
+impl Inner {
+    fn sweep(&mut self) {
+        if self.targets.is_empty() {
+            return;
+        }
+        let Ok(processes) = list_processes() else {
+            return;
+        };
+        let own_pid = std::process::id();
+
+        for (pid, name) in processes {
+            if pid == own_pid || !self.targets.contains(&name) {
+                continue;
+            }
+            match self.action {
+                AppBlockAction::Terminate => {
+                    let _ = terminate(pid);
+                }
+                AppBlockAction::Suspend => {
+                    if self.suspended.iter().any(|p| p.pid == pid) {
+                        continue;
+                    }
+                    if suspend(pid).is_ok() {
+                        self.suspended.push(SuspendedProcess { pid, name });
+                        self.record_suspended();
+                    }
+                }
+            }
+        }
+    }
+
+    fn resume_all(&mut self) -> Result<(), AppError> {
+        let failed = resume_processes(&std::mem::take(&mut self.suspended));
+        self.record_suspended();
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            let names: Vec<String> = failed
+                .iter()
+                .map(|p| format!("{} ({})", p.name, p.pid))
+                .collect();
+            Err(AppError::Io {
+                message: format!("failed to resume {}", names.join(", ")),
+            })
+        }
+    }
+
+    fn record_suspended(&self) {
+        let suspended = self.suspended.clone();
+        let _ = self
+            .journal
+            .update(|entry| entry.suspended_processes = suspended);
+    }
+}
+
+/// Resumes processes frozen by us, skipping any pid that now belongs to a different
+/// program. Returns the ones that couldn't be resumed.
+pub fn resume_processes(processes: &[SuspendedProcess]) -> Vec<SuspendedProcess> {
+    processes
+        .iter()
+        .filter(|process| process_name(process.pid).as_deref() == Some(process.name.as_str()))
+        .filter(|process| resume(process.pid).is_err())
+        .cloned()
+        .collect()
+}
+
+impl ProcessBlocker {
+    pub fn set_journal(&self, journal: Journal) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.journal = journal;
+        }
+    }
+
+    /// Starts (or updates) blocking of `names`. Running matches are handled right away;
+    /// a watcher thread catches applications started later.
+    pub fn enable(&self, names: Vec<String>, action: AppBlockAction) -> Result<(), AppError> {
+        let mut inner = self
+            .inner
+            .lock()
+            .map_err(|_| AppError::poisoned("process blocker"))?;
+
+        if !names.is_empty() {
+            list_processes()?;
+        }
+        if inner.action != action {
+            // Switching to terminate: don't leave anything frozen behind.
+            inner.resume_all()?;
+        }
+        inner.targets = names;
+        inner.action = action;
+        inner.sweep();
+
+        if inner.watcher.is_none() {
+            let stop = Arc::new(AtomicBool::new(false));
+            let watcher = {
+                let inner = self.inner.clone();
+                let stop = stop.clone();
+                thread::spawn(move || watch(inner, stop))
+            };
+            inner.stop = Some(stop);
+            inner.watcher = Some(watcher);
+        }
+        Ok(())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.inner
+            .lock()
+            .map(|inner| inner.watcher.is_some())
+            .unwrap_or(false)
+    }
+
+    /// Stops watching and resumes every process suspended by this blocker.
+    pub fn disable(&self) -> Result<(), AppError> {
+        let (stop, watcher) = {
+            let mut inner = self
+                .inner
+                .lock()
+                .map_err(|_| AppError::poisoned("process blocker"))?;
+            inner.targets.clear();
+            (inner.stop.take(), inner.watcher.take())
+        };
+        // Join outside the lock; the watcher takes it on every tick.
+        if let Some(stop) = stop {
+            stop.store(true, Ordering::SeqCst);
+        }
+        if let Some(watcher) = watcher {
+            let _ = watcher.join();
+        }
+
+        self.inner
+            .lock()
+            .map_err(|_| AppError::poisoned("process blocker"))?
+            .resume_all()
+    }
+}
+
+fn watch(inner: Arc<Mutex<Inner>>, stop: Arc<AtomicBool>) {
+    while !stop.load(Ordering::SeqCst) {
+        thread::sleep(SCAN_INTERVAL);
+        if stop.load(Ordering::SeqCst) {
+            break;
+        }
+        match inner.lock() {
+            Ok(mut inner) => inner.sweep(),
+            Err(_) => break,
+        }
+    }
+}
+
+/// Executable name of a running process: the file name of `/proc/<pid>/exe` when we may
+/// read it, otherwise `comm` (which the kernel truncates to 15 bytes).
+#[cfg(target_os = "linux")]
+fn process_name(pid: u32) -> Option<String> {
+    let exe = std::fs::read_link(format!("/proc/{pid}/exe"))
+        .ok()
+        .and_then(|path| {
+            path.file_name().map(|name| {
+                name.to_string_lossy()
+                    .trim_end_matches(" (deleted)")
+                    .to_string()
+            })
+        });
+    let name = match exe {
+        Some(name) => name,
+        None => std::fs::read_to_string(format!("/proc/{pid}/comm"))
+            .ok()?
+            .trim_end()
+            .to_string(),
+    };
+    normalize_app_name(&name).ok()
+}
+
+/// Executable name from the full image path (`C:\...\Discord.exe` -> `discord`).
+#[cfg(windows)]
+fn process_name(pid: u32) -> Option<String> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::System::Threading::{
+        QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    let process = open_process(pid, PROCESS_QUERY_LIMITED_INFORMATION).ok()?;
+    let mut path = [0u16; 1024];
+    let mut len = path.len() as u32;
+    // SAFETY: `path` has room for `len` UTF-16 units.
+    let ok = unsafe {
+        QueryFullProcessImageNameW(
+            process.as_raw_handle(),
+            PROCESS_NAME_WIN32,
+            path.as_mut_ptr(),
+            &mut len,
+        )
+    };
+    if ok == 0 {
+        return None;
+    }
+    normalize_app_name(&String::from_utf16_lossy(&path[..len as usize])).ok()
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn process_name(_pid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn list_processes() -> Result<Vec<(u32, String)>, AppError> {
+    let entries =
+        std::fs::read_dir("/proc").map_err(|e| AppError::io("failed to list processes", e))?;
+    Ok(entries
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(|pid| Some((pid, process_name(pid)?)))
+        .collect())
+}
+
+#[cfg(windows)]
+fn list_processes() -> Result<Vec<(u32, String)>, AppError> {
+    use std::os::windows::io::{AsRawHandle, FromRawHandle, OwnedHandle};
+    use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+    use windows_sys::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W,
+        TH32CS_SNAPPROCESS,
+    };
+
+    // SAFETY: no preconditions; failure is reported as INVALID_HANDLE_VALUE.
+    let snapshot = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) };
+    if snapshot == INVALID_HANDLE_VALUE {
+        return Err(AppError::io(
+            "failed to list processes",
+            std::io::Error::last_os_error(),
+        ));
+    }
+    // SAFETY: a valid snapshot handle that nothing else owns.
+    let snapshot = unsafe { OwnedHandle::from_raw_handle(snapshot) };
+
+    // SAFETY: PROCESSENTRY32W is plain data; all zeroes is a valid value.
+    let mut entry: PROCESSENTRY32W = unsafe { std::mem::zeroed() };
+    entry.dwSize = std::mem::size_of::<PROCESSENTRY32W>() as u32;
+    let mut processes = Vec::new();
+    // SAFETY: `entry.dwSize` is set as the API requires.
+    let mut found = unsafe { Process32FirstW(snapshot.as_raw_handle(), &mut entry) } != 0;
+    while found {
+        let exe = &entry.szExeFile;
+        let len = exe.iter().position(|&c| c == 0).unwrap_or(exe.len());
+        if let Ok(name) = normalize_app_name(&String::from_utf16_lossy(&exe[..len])) {
+            processes.push((entry.th32ProcessID, name));
+        }
+        // SAFETY: as above.
+        found = unsafe { Process32NextW(snapshot.as_raw_handle(), &mut entry) } != 0;
+    }
+    Ok(processes)
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn list_processes() -> Result<Vec<(u32, String)>, AppError> {
+    Err(AppError::Unsupported {
+        message: "application blocking is only supported on Linux and Windows".to_string(),
+    })
+}
+
+/// Whether this platform can find and stop processes; the blocked-apps UI is hidden otherwise.
+pub const SUPPORTED: bool = cfg!(any(target_os = "linux", windows));
+
+#[cfg(unix)]
+fn send_signal(pid: u32, signal: libc::c_int) -> Result<(), AppError> {
+    let pid = libc::pid_t::try_from(pid)
+        .map_err(|_| AppError::validation("pid", format!("invalid pid {pid}")))?;
+    // SAFETY: kill(2) has no memory-safety preconditions.
+    if unsafe { libc::kill(pid, signal) } == 0 {
+        Ok(())
+    } else {
+        Err(AppError::io(
+            format!("failed to signal process {pid}"),
+            std::io::Error::last_os_error(),
+        ))
+    }
+}
+
+#[cfg(unix)]
+fn terminate(pid: u32) -> Result<(), AppError> {
+    send_signal(pid, libc::SIGTERM)
+}
+
+#[cfg(unix)]
+fn suspend(pid: u32) -> Result<(), AppError> {
+    send_signal(pid, libc::SIGSTOP)
+}
+
+#[cfg(unix)]
+fn resume(pid: u32) -> Result<(), AppError> {
+    send_signal(pid, libc::SIGCONT)
+}
+
+#[cfg(windows)]
+fn open_process(
+    pid: u32,
+    access: windows_sys::Win32::System::Threading::PROCESS_ACCESS_RIGHTS,
+) -> Result<std::os::windows::io::OwnedHandle, AppError> {
+    use std::os::windows::io::{FromRawHandle, OwnedHandle};
+    use windows_sys::Win32::System::Threading::OpenProcess;
+
+    // SAFETY: OpenProcess has no memory-safety preconditions.
+    let handle = unsafe { OpenProcess(access, 0, pid) };
+    if handle.is_null() {
+        return Err(AppError::io(
+            format!("failed to open process {pid}"),
+            std::io::Error::last_os_error(),
+        ));
+    }
+    // SAFETY: a freshly opened handle that nothing else owns.
+    Ok(unsafe { OwnedHandle::from_raw_handle(handle) })
+}
+
+// Not in the SDK headers, but exported by ntdll since Windows XP; it's how Resource Monitor
+// and Process Explorer freeze a whole process.
+#[cfg(windows)]
+#[link(name = "ntdll")]
+extern "system" {
+    fn NtSuspendProcess(process: windows_sys::Win32::Foundation::HANDLE) -> i32;
+    fn NtResumeProcess(process: windows_sys::Win32::Foundation::HANDLE) -> i32;
+}
+
+#[cfg(windows)]
+fn terminate(pid: u32) -> Result<(), AppError> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::System::Threading::{TerminateProcess, PROCESS_TERMINATE};
+
+    let process = open_process(pid, PROCESS_TERMINATE)?;
+    // SAFETY: `process` is a valid handle with PROCESS_TERMINATE access.
+    if unsafe { TerminateProcess(process.as_raw_handle(), 1) } != 0 {
+        Ok(())
+    } else {
+        Err(AppError::io(
+            format!("failed to terminate process {pid}"),
+            std::io::Error::last_os_error(),
+        ))
+    }
+}
+
+#[cfg(windows)]
+fn suspend(pid: u32) -> Result<(), AppError> {
+    suspend_or_resume(pid, NtSuspendProcess, "suspend")
+}
+
+#[cfg(windows)]
+fn resume(pid: u32) -> Result<(), AppError> {
+    suspend_or_resume(pid, NtResumeProcess, "resume")
+}
+
+#[cfg(windows)]
+fn suspend_or_resume(
+    pid: u32,
+    call: unsafe extern "system" fn(windows_sys::Win32::Foundation::HANDLE) -> i32,
+    what: &str,
+) -> Result<(), AppError> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::System::Threading::PROCESS_SUSPEND_RESUME;
+
+    let process = open_process(pid, PROCESS_SUSPEND_RESUME)?;
+    // SAFETY: `process` is a valid handle with PROCESS_SUSPEND_RESUME access.
+    let status = unsafe { call(process.as_raw_handle()) };
+    // Negative NTSTATUS values are errors.
+    if status >= 0 {
+        Ok(())
+    } else {
+        Err(AppError::Io {
+            message: format!("failed to {what} process {pid} (NTSTATUS {status:#010x})"),
+        })
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn terminate(_pid: u32) -> Result<(), AppError> {
+    Err(AppError::Unsupported {
+        message: "application blocking is only supported on Linux and Windows".to_string(),
+    })
+}
+
+#[cfg(not(any(unix, windows)))]
+fn suspend(_pid: u32) -> Result<(), AppError> {
+    Err(AppError::Unsupported {
+        message: "application blocking is only supported on Linux and Windows".to_string(),
+    })
+}
+
+#[cfg(not(any(unix, windows)))]
+fn resume(_pid: u32) -> Result<(), AppError> {
+    Err(AppError::Unsupported {
+        message: "application blocking is only supported on Linux and Windows".to_string(),
+    })
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::focus_blocker::{DirectHosts, FocusBlocker, MemoryHostsStore};
+    use std::path::PathBuf;
+    use std::process::{Child, Command};
+    use std::time::Instant;
+
+    /// Copies `sleep` to a uniquely named executable so only our child matches.
+    fn spawn_distraction(tag: &str) -> (Child, String, PathBuf) {
+        let name = format!("fb-{tag}-{}", std::process::id());
+        let dir = std::env::temp_dir().join(format!("focus-timer-{name}"));
+        std::fs::create_dir_all(&dir).unwrap();
+        let exe = dir.join(&name);
+        std::fs::copy("/bin/sleep", &exe).unwrap();
+        let child = Command::new(&exe).arg("30").spawn().unwrap();
+        // Wait until the exec has happened and /proc shows the new name.
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while process_name(child.id()).as_deref() != Some(name.as_str()) {
+            assert!(Instant::now() < deadline, "child never showed up in /proc");
+            thread::sleep(Duration::from_millis(10));
+        }
+        (child, name, dir)
+    }
+
+    fn state(pid: u32) -> char {
+        let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).unwrap();
+        // The state follows the parenthesised command name.
+        stat[stat.rfind(')').unwrap() + 2..].chars().next().unwrap()
+    }
+
+    fn wait_for_state(pid: u32, wanted: &[char]) -> char {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            let current = state(pid);
+            if wanted.contains(&current) || Instant::now() > deadline {
+                return current;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn normalizes_app_names() {
+        assert_eq!(normalize_app_name(" Discord.exe ").unwrap(), "discord");
+        assert_eq!(normalize_app_name("/usr/bin/steam").unwrap(), "steam");
+        assert_eq!(normalize_app_name(r"C:\Games\Steam.EXE").unwrap(), "steam");
+        assert!(normalize_app_name("  ").is_err());
+    }
+
+    #[test]
+    fn terminates_matching_processes() {
+        let (mut child, name, dir) = spawn_distraction("term");
+        let blocker = ProcessBlocker::default();
+
+        blocker
+            .enable(vec![name], AppBlockAction::Terminate)
+            .unwrap();
+        let status = child.wait().unwrap();
+        assert!(!status.success());
+
+        blocker.disable().unwrap();
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn suspends_and_resumes_on_disable() {
+        let (mut child, name, dir) = spawn_distraction("stop");
+        let pid = child.id();
+        let blocker = ProcessBlocker::default();
+
+        blocker.enable(vec![name], AppBlockAction::Suspend).unwrap();
+        assert_eq!(wait_for_state(pid, &['T']), 'T');
+
+        blocker.disable().unwrap();
+        assert!(matches!(wait_for_state(pid, &['S', 'R']), 'S' | 'R'));
+
+        let _ = child.kill();
+        let _ = child.wait();
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn restart_resumes_processes_left_frozen_by_a_crash() {
+        let (mut child, name, dir) = spawn_distraction("crash");
+        let pid = child.id();
+        let journal_path = dir.join("journal.json");
+
+        let blocker = ProcessBlocker::default();
+        blocker.set_journal(Journal::open(&journal_path));
+        blocker.enable(vec![name], AppBlockAction::Suspend).unwrap();
+        assert_eq!(wait_for_state(pid, &['T']), 'T');
+
+        // Simulate a crash: the watcher goes away without resuming anything.
+        let (stop, watcher) = {
+            let mut inner = blocker.inner.lock().unwrap();
+            (inner.stop.take(), inner.watcher.take())
+        };
+        stop.unwrap().store(true, Ordering::SeqCst);
+        watcher.unwrap().join().unwrap();
+        drop(blocker);
+
+        let restarted =
+            FocusBlocker::with_hosts_backend(DirectHosts::new(MemoryHostsStore::new(""), None));
+        restarted.set_journal(Journal::open(&journal_path));
+        restarted.ensure_disabled();
+        assert!(matches!(wait_for_state(pid, &['S', 'R']), 'S' | 'R'));
+        assert!(!journal_path.exists());
+
+        let _ = child.kill();
+        let _ = child.wait();
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use chrono::{Datelike, Duration, NaiveDateTime, NaiveTime};
+
+use crate::error::AppError;
+use crate::models::BlockSchedule;
+
+/// Something that wants focus-mode blocking on. Blocking stays on while any source holds it,
+/// so a schedule window and the timer can overlap without one turning the other's block off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HoldSource {
+    /// A running work phase.
+    Timer,
+    /// A scheduled blocking window.
+    Schedule,
+}
+
+/// Shared set of current [`HoldSource`]s; cheap to clone. The `enable`/`disable` callbacks
+/// run with the set locked, so the timer worker and the scheduler can't interleave a release
+/// with a hold and leave blocking off while someone still holds it.
+#[derive(Clone, Default)]
+pub struct BlockingHolds {
+    inner: Arc<Mutex<HashSet<HoldSource>>>,
+}
+
+impl BlockingHolds {
+    /// Adds `source`'s hold and (re)applies blocking.
+    pub fn hold(&self, source: HoldSource, enable: impl FnOnce()) -> Result<(), AppError> {
+        let mut holds = self
+            .inner
+            .lock()
+            .map_err(|_| AppError::poisoned("blocking holds"))?;
+        holds.insert(source);
+        enable();
+        Ok(())
+    }
+
+    /// Drops `source`'s hold and turns blocking off if no other source holds it.
+    pub fn release(&self, source: HoldSource, disable: impl FnOnce()) -> Result<(), AppError> {
+        let mut holds = self
+            .inner
+            .lock()
+            .map_err(|_| AppError::poisoned("blocking holds"))?;
+        holds.remove(&source);
+        if holds.is_empty() {
+            disable();
+        }
+        Ok(())
+    }
+
+    pub fn release_all(&self, disable: impl FnOnce()) -> Result<(), AppError> {
+        let mut holds = self
+            .inner
+            .lock()
+            .map_err(|_| AppError::poisoned("blocking holds"))?;
+        holds.clear();
+        disable();
+        Ok(())
+    }
+
+    pub fn is_held_by(&self, source: HoldSource) -> bool {
+        self.inner
+            .lock()
+            .map(|holds| holds.contains(&source))
+            .unwrap_or(false)
+    }
+}
+
+/// Parses a local "HH:MM" time.
+pub fn parse_time(field: &str, value: &str) -> Result<NaiveTime, AppError> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .map_err(|_| AppError::validation(field, format!("{value:?} is not a time like 09:00")))
+}
+
+/// Checks a schedule from the UI and puts it in stored form: days sorted and deduplicated,
+/// times as zero-padded "HH:MM".
+pub fn normalize_schedule(
+    days: &[u8],
+    start: &str,
+    end: &str,
+) -> Result<(Vec<u8>, String, String), AppError> {
+    let mut days = days.to_vec();
+    days.sort_unstable();
+    days.dedup();
+    if days.is_empty() {
+        return Err(AppError::validation("days", "pick at least one day"));
+    }
+    if days.iter().any(|&day| day > 6) {
+        return Err(AppError::validation(
+            "days",
+            "days run from 0 (Monday) to 6 (Sunday)",
+        ));
+    }
+    let start = parse_time("start", start)?;
+    let end = parse_time("end", end)?;
+    if start == end {
+        return Err(AppError::validation("end", "the window must not be empty"));
+    }
+    Ok((
+        days,
+        start.format("%H:%M").to_string(),
+        end.format("%H:%M").to_string(),
+    ))
+}
+
+/// Whether `schedule` covers the local time `now`. A window whose end is before its start
+/// runs past midnight and belongs to the day it starts on.
+pub fn is_active(schedule: &BlockSchedule, now: NaiveDateTime) -> bool {
+    if !schedule.enabled {
+        return false;
+    }
+    let (Ok(start), Ok(end)) = (
+        parse_time("start", &schedule.start),
+        parse_time("end", &schedule.end),
+    ) else {
+        return false;
+    };
+    let on = |date: NaiveDateTime| {
+        let day = date.weekday().num_days_from_monday() as u8;
+        schedule.days.contains(&day)
+    };
+    let time = now.time();
+    if start < end {
+        on(now) && start <= time && time < end
+    } else {
+        (on(now) && time >= start) || (on(now - Duration::days(1)) && time < end)
+    }
+}
+
+pub fn any_active(schedules: &[BlockSchedule], now: NaiveDateTime) -> bool {
+    schedules.iter().any(|schedule| is_active(schedule, now))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn schedule(days: &[u8], start: &str, end: &str) -> BlockSchedule {
+        let (days, start, end) = normalize_schedule(days, start, end).unwrap();
+        BlockSchedule {
+            id: 1,
+            days,
+            start,
+            end,
+            enabled: true,
+        }
+    }
+
+    /// 2024-01-01 was a Monday.
+    fn at(day: u32, time: &str) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, day)
+            .unwrap()
+            .and_time(parse_time("time", time).unwrap())
+    }
+
+    #[test]
+    fn weekday_mornings() {
+        let mornings = schedule(&[4, 3, 2, 1, 0, 0], "9:00", "12:00");
+        assert_eq!(mornings.days, vec![0, 1, 2, 3, 4]);
+        assert_eq!(mornings.start, "09:00");
+
+        assert!(!is_active(&mornings, at(1, "08:59")));
+        assert!(is_active(&mornings, at(1, "09:00")));
+        assert!(is_active(&mornings, at(5, "11:59")));
+        assert!(!is_active(&mornings, at(5, "12:00")));
+        // Saturday.
+        assert!(!is_active(&mornings, at(6, "10:00")));
+
+        let disabled = BlockSchedule {
+            enabled: false,
+            ..mornings
+        };
+        assert!(!is_active(&disabled, at(1, "10:00")));
+    }
+
+    #[test]
+    fn overnight_window_belongs_to_its_start_day() {
+        // Friday night into Saturday morning.
+        let late = schedule(&[4], "22:00", "02:00");
+        assert!(!is_active(&late, at(4, "23:00")));
+        assert!(is_active(&late, at(5, "23:00")));
+        assert!(is_active(&late, at(6, "01:30")));
+        assert!(!is_active(&late, at(6, "02:00")));
+        assert!(!is_active(&late, at(6, "23:00")));
+    }
+
+    #[test]
+    fn rejects_bad_schedules() {
+        assert!(matches!(
+            normalize_schedule(&[], "09:00", "12:00"),
+            Err(AppError::Validation { field, .. }) if field == "days"
+        ));
+        assert!(normalize_schedule(&[7], "09:00", "12:00").is_err());
+        assert!(normalize_schedule(&[0], "9am", "12:00").is_err());
+        assert!(normalize_schedule(&[0], "09:00", "09:00").is_err());
+    }
+
+    #[test]
+    fn blocking_stays_on_until_the_last_hold_is_released() {
+        let holds = BlockingHolds::default();
+        let enabled = std::cell::Cell::new(false);
+        holds.hold(HoldSource::Timer, || enabled.set(true)).unwrap();
+        holds
+            .hold(HoldSource::Schedule, || enabled.set(true))
+            .unwrap();
+
+        // The timer's break starts inside the scheduled window.
+        holds
+            .release(HoldSource::Timer, || enabled.set(false))
+            .unwrap();
+        assert!(enabled.get());
+        assert!(holds.is_held_by(HoldSource::Schedule));
+
+        holds
+            .release(HoldSource::Schedule, || enabled.set(false))
+            .unwrap();
+        assert!(!enabled.get());
+    }
+}
@@ -0,0 +1,136 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+use crate::error::AppError;
+
+/// Typed to lift a locked session right away.
+pub const UNLOCK_PHRASE: &str = "I am choosing to give up on this focus session";
+/// Without the phrase, the lock lifts this long after an unlock was requested.
+pub const UNLOCK_COOLDOWN_MINUTES: i64 = 10;
+
+/// A work session started in locked mode: pausing, resetting or closing the app doesn't
+/// lift blocking until `ends_at`, short of an emergency unlock.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionLock {
+    pub ends_at: DateTime<Utc>,
+    pub unlock_requested_at: Option<DateTime<Utc>>,
+}
+
+/// What the UI shows about the lock.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionLockStatus {
+    pub ends_at: String,
+    /// When the cooldown unlock becomes possible, once one was requested.
+    pub unlock_available_at: Option<String>,
+    pub unlock_phrase: &'static str,
+    pub cooldown_minutes: i64,
+}
+
+impl SessionLock {
+    pub fn new(now: DateTime<Utc>, remaining_seconds: u32) -> Self {
+        Self {
+            ends_at: now + Duration::seconds(i64::from(remaining_seconds)),
+            unlock_requested_at: None,
+        }
+    }
+
+    /// The locked work phase is over, so the lock no longer applies.
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        now >= self.ends_at
+    }
+
+    /// Seconds of the locked work phase still to go.
+    pub fn remaining_seconds(&self, now: DateTime<Utc>) -> u32 {
+        u32::try_from((self.ends_at - now).num_seconds().max(0)).unwrap_or(u32::MAX)
+    }
+
+    /// Starts the cooldown; asking again doesn't restart it.
+    pub fn request_unlock(&mut self, now: DateTime<Utc>) {
+        self.unlock_requested_at.get_or_insert(now);
+    }
+
+    pub fn unlock_available_at(&self) -> Option<DateTime<Utc>> {
+        self.unlock_requested_at
+            .map(|at| at + Duration::minutes(UNLOCK_COOLDOWN_MINUTES))
+    }
+
+    /// Succeeds if `phrase` is the unlock phrase or the cooldown has run out.
+    pub fn try_unlock(&self, now: DateTime<Utc>, phrase: Option<&str>) -> Result<(), AppError> {
+        if phrase.is_some_and(|p| p.trim() == UNLOCK_PHRASE) {
+            return Ok(());
+        }
+        match self.unlock_available_at() {
+            Some(at) if now >= at => Ok(()),
+            Some(at) => Err(AppError::SessionLocked {
+                message: format!(
+                    "the unlock cooldown runs for another {} seconds; type the unlock phrase to skip it",
+                    (at - now).num_seconds()
+                ),
+            }),
+            None if phrase.is_some() => Err(AppError::validation(
+                "phrase",
+                "that isn't the unlock phrase",
+            )),
+            None => Err(AppError::SessionLocked {
+                message: "request an unlock first, or type the unlock phrase".to_string(),
+            }),
+        }
+    }
+
+    pub fn status(&self) -> SessionLockStatus {
+        SessionLockStatus {
+            ends_at: self.ends_at.to_rfc3339(),
+            unlock_available_at: self.unlock_available_at().map(|at| at.to_rfc3339()),
+            unlock_phrase: UNLOCK_PHRASE,
+            cooldown_minutes: UNLOCK_COOLDOWN_MINUTES,
+        }
+    }
+}
+
+pub fn locked_error(action: &str) -> AppError {
+    AppError::SessionLocked {
+        message: format!("can't {action} during a locked work session"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap()
+    }
+
+    #[test]
+    fn expires_when_the_work_phase_would_end() {
+        let lock = SessionLock::new(at(0), 25 * 60);
+        assert!(!lock.is_expired(at(25 * 60 - 1)));
+        assert_eq!(lock.remaining_seconds(at(60)), 24 * 60);
+        assert!(lock.is_expired(at(25 * 60)));
+        assert_eq!(lock.remaining_seconds(at(30 * 60)), 0);
+    }
+
+    #[test]
+    fn phrase_unlocks_immediately() {
+        let lock = SessionLock::new(at(0), 25 * 60);
+        assert!(lock.try_unlock(at(1), Some("let me out")).is_err());
+        assert!(lock.try_unlock(at(1), None).is_err());
+        lock.try_unlock(at(1), Some(&format!(" {UNLOCK_PHRASE} ")))
+            .unwrap();
+    }
+
+    #[test]
+    fn cooldown_unlocks_after_waiting() {
+        let mut lock = SessionLock::new(at(0), 25 * 60);
+        lock.request_unlock(at(60));
+        // Asking again must not push the cooldown back.
+        lock.request_unlock(at(120));
+        let cooldown = UNLOCK_COOLDOWN_MINUTES * 60;
+        assert!(matches!(
+            lock.try_unlock(at(60 + cooldown - 1), None),
+            Err(AppError::SessionLocked { .. })
+        ));
+        lock.try_unlock(at(60 + cooldown), None).unwrap();
+    }
+}
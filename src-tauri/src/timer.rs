@@ -1,3 +1,4 @@
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -29,6 +30,17 @@ impl Default for TimerConfig {
     }
 }
 
+impl Phase {
+    /// Same as the serde name; used for `sessions.phase_type`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Phase::Work => "work",
+            Phase::ShortBreak => "short_break",
+            Phase::LongBreak => "long_break",
+        }
+    }
+}
+
 impl TimerConfig {
     pub fn seconds_for(self, phase: Phase) -> u32 {
         match phase {
@@ -49,6 +61,10 @@ pub struct TimerState {
     pub completed_work_sessions: u32,
     pub long_break_after: u32,
     pub daily_goal: u32,
+    /// When the running phase ends. `remaining_seconds` is derived from this on every tick,
+    /// so a slow or suspended worker thread can't make the timer drift.
+    #[serde(skip)]
+    pub ends_at: Option<DateTime<Utc>>,
 }
 
 impl TimerState {
@@ -62,30 +78,71 @@ impl TimerState {
             completed_work_sessions: 0,
             long_break_after: cfg.long_break_after,
             daily_goal: cfg.daily_goal,
+            ends_at: None,
+        }
+    }
+
+    /// Runs the current phase from `now` for whatever is left of it.
+    pub fn start(&mut self, now: DateTime<Utc>) {
+        if !self.is_running {
+            self.is_running = true;
+            self.ends_at = Some(now + Duration::seconds(i64::from(self.remaining_seconds)));
         }
     }
 
+    pub fn pause(&mut self, now: DateTime<Utc>) {
+        self.sync_remaining(now);
+        self.is_running = false;
+        self.ends_at = None;
+    }
+
     pub fn reset_current_phase(&mut self) {
         self.remaining_seconds = self.total_seconds;
         self.is_running = false;
+        self.ends_at = None;
     }
 
-    pub fn apply_phase(&mut self, phase: Phase, cfg: TimerConfig) {
+    /// Switches to `phase` at its full length. A running timer keeps running from `now`.
+    pub fn apply_phase(&mut self, phase: Phase, cfg: TimerConfig, now: DateTime<Utc>) {
         self.phase = phase;
         self.total_seconds = cfg.seconds_for(phase);
         self.remaining_seconds = self.total_seconds;
+        if self.is_running {
+            self.ends_at = Some(now + Duration::seconds(i64::from(self.total_seconds)));
+        }
     }
 
-    pub fn tick(&mut self, cfg: TimerConfig) -> Option<PhaseCompleteEvent> {
-        if !self.is_running {
-            return None;
+    /// How long the current phase has run so far, not counting time spent paused.
+    pub fn elapsed_seconds(&self, now: DateTime<Utc>) -> u32 {
+        let remaining = match self.ends_at {
+            Some(ends_at) if self.is_running => seconds_until(now, ends_at),
+            _ => self.remaining_seconds,
+        };
+        self.total_seconds.saturating_sub(remaining)
+    }
+
+    fn sync_remaining(&mut self, now: DateTime<Utc>) {
+        if let Some(ends_at) = self.ends_at {
+            self.remaining_seconds = seconds_until(now, ends_at);
         }
+    }
 
-        if self.remaining_seconds > 0 {
-            self.remaining_seconds -= 1;
+    /// Brings the timer up to `now`. When the running phase has ended, moves to the next one
+    /// as if it had started exactly at that end, so a late tick loses no time.
+    ///
+    /// After a suspend (or any long stall) the next phase may be over as well. That one
+    /// went by unseen, so it isn't counted: the timer stops on a fresh work phase and waits
+    /// for the user instead of chaining through phases nobody was there for.
+    pub fn tick(&mut self, cfg: TimerConfig, now: DateTime<Utc>) -> Option<PhaseCompleteEvent> {
+        if !self.is_running {
+            return None;
         }
+        let ends_at = *self
+            .ends_at
+            .get_or_insert(now + Duration::seconds(i64::from(self.remaining_seconds)));
 
-        if self.remaining_seconds != 0 {
+        if now < ends_at {
+            self.sync_remaining(now);
             return None;
         }
 
@@ -95,7 +152,9 @@ impl TimerState {
             Phase::Work => {
                 self.completed_work_sessions = self.completed_work_sessions.saturating_add(1);
                 if cfg.long_break_after > 0
-                    && self.completed_work_sessions % cfg.long_break_after == 0
+                    && self
+                        .completed_work_sessions
+                        .is_multiple_of(cfg.long_break_after)
                 {
                     Phase::LongBreak
                 } else {
@@ -105,14 +164,61 @@ impl TimerState {
             Phase::ShortBreak | Phase::LongBreak => Phase::Work,
         };
 
-        self.apply_phase(to, cfg);
+        self.phase = to;
+        self.total_seconds = cfg.seconds_for(to);
+        let next_ends_at = ends_at + Duration::seconds(i64::from(self.total_seconds));
+        if now < next_ends_at {
+            self.ends_at = Some(next_ends_at);
+            self.sync_remaining(now);
+        } else {
+            self.is_running = false;
+            self.apply_phase(Phase::Work, cfg, now);
+            self.ends_at = None;
+        }
 
         Some(PhaseCompleteEvent {
             from,
-            to,
+            to: self.phase,
             completed_seconds,
+            ended_at: ends_at,
+            skipped: false,
         })
     }
+
+    /// Where `skip_phase` goes from the current phase. Skipping work never earns a long
+    /// break; jump there explicitly for that.
+    pub fn skip_target(&self) -> Phase {
+        match self.phase {
+            Phase::Work => Phase::ShortBreak,
+            Phase::ShortBreak | Phase::LongBreak => Phase::Work,
+        }
+    }
+
+    /// Abandons the current phase and moves to `to` at its full length, keeping the timer
+    /// running or paused as it was. The abandoned phase doesn't count as completed.
+    pub fn skip_to(
+        &mut self,
+        to: Phase,
+        cfg: TimerConfig,
+        now: DateTime<Utc>,
+    ) -> PhaseCompleteEvent {
+        let from = self.phase;
+        let elapsed = self.elapsed_seconds(now);
+        self.apply_phase(to, cfg, now);
+        PhaseCompleteEvent {
+            from,
+            to,
+            completed_seconds: elapsed,
+            ended_at: now,
+            skipped: true,
+        }
+    }
+}
+
+/// Whole seconds left until `ends_at`, rounded up so the display reaches 0 only at the end.
+fn seconds_until(now: DateTime<Utc>, ends_at: DateTime<Utc>) -> u32 {
+    let millis = (ends_at - now).num_milliseconds().max(0);
+    u32::try_from((millis + 999) / 1000).unwrap_or(u32::MAX)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -120,5 +226,215 @@ impl TimerState {
 pub struct PhaseCompleteEvent {
     pub from: Phase,
     pub to: Phase,
+    /// Time spent in `from`; for a skipped phase only the part that actually ran.
     pub completed_seconds: u32,
+    /// When the completed phase ran out, which after a suspend is earlier than the event.
+    #[serde(skip, default = "Utc::now")]
+    pub ended_at: DateTime<Utc>,
+    /// `from` was cut short by `skip_phase`/`set_phase` rather than running out.
+    #[serde(default)]
+    pub skipped: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, FakeClock};
+
+    /// Short phases so the cadence tests read easily: 25s work, 5s/15s breaks.
+    fn cfg() -> TimerConfig {
+        TimerConfig {
+            work_seconds: 25,
+            short_break_seconds: 5,
+            long_break_seconds: 15,
+            long_break_after: 4,
+            daily_goal: 8,
+        }
+    }
+
+    fn started(clock: &FakeClock, cfg: TimerConfig) -> TimerState {
+        let mut state = TimerState::new(cfg);
+        state.start(clock.now());
+        state
+    }
+
+    /// Advances the clock a second at a time, like the worker would, and collects the
+    /// phase changes.
+    fn run_for(
+        state: &mut TimerState,
+        clock: &FakeClock,
+        cfg: TimerConfig,
+        seconds: i64,
+    ) -> Vec<(Phase, Phase)> {
+        let mut events = Vec::new();
+        for _ in 0..seconds {
+            clock.advance_secs(1);
+            if let Some(evt) = state.tick(cfg, clock.now()) {
+                events.push((evt.from, evt.to));
+            }
+        }
+        events
+    }
+
+    #[test]
+    fn phase_names_match_serde() {
+        for phase in [Phase::Work, Phase::ShortBreak, Phase::LongBreak] {
+            assert_eq!(
+                serde_json::to_value(phase).unwrap(),
+                serde_json::Value::from(phase.as_str())
+            );
+        }
+    }
+
+    #[test]
+    fn work_phase_completes_into_a_short_break() {
+        let clock = FakeClock::at("2024-01-01T09:00:00Z");
+        let mut state = started(&clock, cfg());
+
+        assert!(run_for(&mut state, &clock, cfg(), 24).is_empty());
+        assert_eq!(state.remaining_seconds, 1);
+
+        let events = run_for(&mut state, &clock, cfg(), 1);
+        assert_eq!(events, vec![(Phase::Work, Phase::ShortBreak)]);
+        assert_eq!(state.completed_work_sessions, 1);
+        assert_eq!(state.remaining_seconds, 5);
+        assert!(state.is_running);
+    }
+
+    #[test]
+    fn every_fourth_work_session_earns_a_long_break() {
+        let clock = FakeClock::at("2024-01-01T09:00:00Z");
+        let mut state = started(&clock, cfg());
+
+        // Three rounds of work + short break, then work into a long break, then back to work.
+        let events = run_for(&mut state, &clock, cfg(), 3 * (25 + 5) + 25 + 15);
+        assert_eq!(
+            events,
+            vec![
+                (Phase::Work, Phase::ShortBreak),
+                (Phase::ShortBreak, Phase::Work),
+                (Phase::Work, Phase::ShortBreak),
+                (Phase::ShortBreak, Phase::Work),
+                (Phase::Work, Phase::ShortBreak),
+                (Phase::ShortBreak, Phase::Work),
+                (Phase::Work, Phase::LongBreak),
+                (Phase::LongBreak, Phase::Work),
+            ]
+        );
+        assert_eq!(state.completed_work_sessions, 4);
+
+        // The cadence starts over.
+        let events = run_for(&mut state, &clock, cfg(), 25);
+        assert_eq!(events, vec![(Phase::Work, Phase::ShortBreak)]);
+    }
+
+    #[test]
+    fn long_breaks_can_be_turned_off() {
+        let cfg = TimerConfig {
+            long_break_after: 0,
+            ..cfg()
+        };
+        let clock = FakeClock::at("2024-01-01T09:00:00Z");
+        let mut state = started(&clock, cfg);
+
+        let events = run_for(&mut state, &clock, cfg, 8 * (25 + 5));
+        assert!(events.iter().all(|&(_, to)| to != Phase::LongBreak));
+        assert_eq!(state.completed_work_sessions, 8);
+    }
+
+    #[test]
+    fn skipping_abandons_the_phase_without_counting_it() {
+        let clock = FakeClock::at("2024-01-01T09:00:00Z");
+        let mut state = started(&clock, cfg());
+        run_for(&mut state, &clock, cfg(), 10);
+
+        let evt = state.skip_to(state.skip_target(), cfg(), clock.now());
+        assert_eq!((evt.from, evt.to), (Phase::Work, Phase::ShortBreak));
+        assert!(evt.skipped);
+        assert_eq!(evt.completed_seconds, 10);
+        assert_eq!(state.completed_work_sessions, 0);
+        // The break runs at full length from the moment of the skip.
+        assert!(state.is_running);
+        assert_eq!(state.remaining_seconds, 5);
+        assert_eq!(
+            run_for(&mut state, &clock, cfg(), 5),
+            vec![(Phase::ShortBreak, Phase::Work)]
+        );
+    }
+
+    #[test]
+    fn jumping_to_a_phase_keeps_a_paused_timer_paused() {
+        let clock = FakeClock::at("2024-01-01T09:00:00Z");
+        let mut state = TimerState::new(cfg());
+
+        let evt = state.skip_to(Phase::LongBreak, cfg(), clock.now());
+        assert_eq!(evt.completed_seconds, 0);
+        assert_eq!(state.phase, Phase::LongBreak);
+        assert_eq!(state.remaining_seconds, 15);
+        assert!(!state.is_running);
+        assert_eq!(state.skip_target(), Phase::Work);
+    }
+
+    #[test]
+    fn remaining_time_follows_the_clock_not_the_ticks() {
+        let cfg = TimerConfig::default();
+        let clock = FakeClock::at("2024-01-01T09:00:00Z");
+        let mut state = started(&clock, cfg);
+
+        // One late tick after 90 seconds accounts for all of them.
+        clock.advance_secs(90);
+        assert!(state.tick(cfg, clock.now()).is_none());
+        assert_eq!(state.remaining_seconds, 25 * 60 - 90);
+
+        clock.advance_secs(10);
+        assert_eq!(state.elapsed_seconds(clock.now()), 100);
+        state.pause(clock.now());
+        assert_eq!(state.remaining_seconds, 25 * 60 - 100);
+        // Time spent paused doesn't count.
+        clock.advance_secs(900);
+        assert!(state.tick(cfg, clock.now()).is_none());
+        state.start(clock.now());
+        clock.advance_secs(10);
+        state.tick(cfg, clock.now());
+        assert_eq!(state.remaining_seconds, 25 * 60 - 110);
+        assert_eq!(state.elapsed_seconds(clock.now()), 110);
+    }
+
+    #[test]
+    fn next_phase_starts_when_the_last_one_ended() {
+        let cfg = TimerConfig::default();
+        let clock = FakeClock::at("2024-01-01T09:00:00Z");
+        let mut state = started(&clock, cfg);
+        let work_ends = clock.now() + Duration::minutes(25);
+
+        // The tick arrives 30 seconds after the work phase ran out.
+        clock.advance_secs(25 * 60 + 30);
+        let evt = state.tick(cfg, clock.now()).unwrap();
+        assert_eq!((evt.from, evt.to), (Phase::Work, Phase::ShortBreak));
+        assert_eq!(evt.ended_at, work_ends);
+        assert!(state.is_running);
+        assert_eq!(state.remaining_seconds, 5 * 60 - 30);
+    }
+
+    #[test]
+    fn phases_that_passed_while_suspended_are_not_chained() {
+        let cfg = TimerConfig::default();
+        let clock = FakeClock::at("2024-01-01T09:00:00Z");
+        let mut state = started(&clock, cfg);
+        let work_ends = clock.now() + Duration::minutes(25);
+        run_for(&mut state, &clock, cfg, 10);
+
+        // Laptop lid closed for an hour: the work phase completed, the break went by unseen.
+        clock.advance(Duration::hours(1));
+        let evt = state.tick(cfg, clock.now()).unwrap();
+        assert_eq!(evt.from, Phase::Work);
+        assert_eq!(evt.ended_at, work_ends);
+        assert_eq!(state.completed_work_sessions, 1);
+        assert_eq!(state.phase, Phase::Work);
+        assert!(!state.is_running);
+        assert_eq!(state.remaining_seconds, 25 * 60);
+
+        clock.advance(Duration::hours(1));
+        assert!(state.tick(cfg, clock.now()).is_none());
+    }
 }
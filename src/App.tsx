@@ -1,19 +1,41 @@
 import "./App.css";
 import { CircularTimer } from "./components/CircularTimer";
 import { TimerControls } from "./components/TimerControls";
+import { SessionLockPanel } from "./components/SessionLockPanel";
 import { StatsDashboard } from "./components/StatsDashboard";
 import { SessionTimeline } from "./components/SessionTimeline";
 import { Settings } from "./components/Settings";
 import { useTimer } from "./hooks/useTimer";
 import { useTimerStore } from "./store/timerStore";
+import type { BlockerStatus } from "./types/timer";
+import { errorMessage } from "./types/error";
 import { useState } from "react";
 
+function blockerSummary(status: BlockerStatus): string {
+  const via = { hosts: "hosts file", dns: "DNS sinkhole", proxy: "proxy" }[status.backend];
+  const sites = `${status.domainCount} site${status.domainCount === 1 ? "" : "s"}`;
+  const parts = [
+    status.allowlist ? `Allowing only ${sites} (${via})` : `Blocking ${sites} (${via})`,
+  ];
+  if (status.notifications === "suppressed") parts.push("notifications silenced");
+  if (status.notifications === "unavailable") parts.push("notifications couldn't be silenced");
+  return parts.join(" · ");
+}
+
 function App() {
-  const { start, pause, reset } = useTimer();
+  const { start, pause, reset, skip, setPhase, restoreHostsBackup, requestUnlock, unlock } =
+    useTimer();
   const blockingError = useTimerStore((s) => s.blockingError);
+  const blockerStatus = useTimerStore((s) => s.blockerStatus);
   const setBlockingError = useTimerStore((s) => s.setBlockingError);
+  const hostsRecovery = useTimerStore((s) => s.hostsRecovery);
+  const setHostsRecovery = useTimerStore((s) => s.setHostsRecovery);
   const [showSettings, setShowSettings] = useState(false);
 
+  // Pause/reset are refused during a locked session; say why instead of doing nothing.
+  const reportErrors = (action: () => Promise<void>) => () =>
+    action().catch((e) => setBlockingError(errorMessage(e)));
+
   return (
     <main className="app">
       {/* Settings Button */}
@@ -41,12 +63,53 @@ function App() {
         </div>
       )}
 
+      {/* Hosts Recovery Banner */}
+      {hostsRecovery && (
+        <div className="error-banner" role="alert">
+          <div className="error-banner__text">
+            The hosts file ({hostsRecovery.hostsPath}) looks damaged: {hostsRecovery.reason}
+          </div>
+          {hostsRecovery.backupPath && (
+            <button
+              type="button"
+              className="btn btn--secondary"
+              onClick={() =>
+                void restoreHostsBackup()
+                  .then(() => setHostsRecovery(null))
+                  .catch((e) => setBlockingError(errorMessage(e)))
+              }
+            >
+              Restore backup
+            </button>
+          )}
+          <button
+            type="button"
+            className="error-banner__close"
+            onClick={() => setHostsRecovery(null)}
+            aria-label="Dismiss"
+          >
+            ✕
+          </button>
+        </div>
+      )}
+
       {/* Main Content */}
       <div className="app__container">
         {/* Left Column - Timer & Controls */}
         <section className="timer-section">
           <CircularTimer />
-          <TimerControls onStart={start} onPause={pause} onReset={reset} />
+          <TimerControls
+            onStart={reportErrors(() => start())}
+            onStartLocked={reportErrors(() => start(true))}
+            onPause={reportErrors(pause)}
+            onReset={reportErrors(reset)}
+            onSkip={reportErrors(skip)}
+            onSetPhase={(phase) => reportErrors(() => setPhase(phase))()}
+          />
+          <SessionLockPanel onRequestUnlock={requestUnlock} onUnlock={unlock} />
+          {blockerStatus?.enabled && (
+            <p className="blocker-status">{blockerSummary(blockerStatus)}</p>
+          )}
         </section>
 
         {/* Right Column - Dashboard & Timeline */}
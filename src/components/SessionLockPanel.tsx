@@ -0,0 +1,77 @@
+import { useEffect, useState } from "react";
+import { useTimerStore } from "../store/timerStore";
+import { errorMessage } from "../types/error";
+
+interface SessionLockPanelProps {
+    onRequestUnlock: () => Promise<void>;
+    onUnlock: (phrase?: string) => Promise<void>;
+}
+
+function clockTime(iso: string): string {
+    return new Date(iso).toLocaleTimeString([], { hour: "2-digit", minute: "2-digit" });
+}
+
+/** Shown while a locked work session runs; offers the emergency unlock. */
+export function SessionLockPanel({ onRequestUnlock, onUnlock }: SessionLockPanelProps) {
+    const lock = useTimerStore((s) => s.sessionLock);
+    const [phrase, setPhrase] = useState("");
+    const [error, setError] = useState<string | null>(null);
+    const [now, setNow] = useState(() => Date.now());
+
+    // Re-render once the cooldown may have run out.
+    useEffect(() => {
+        if (!lock?.unlockAvailableAt) return;
+        const id = window.setInterval(() => setNow(Date.now()), 1000);
+        return () => window.clearInterval(id);
+    }, [lock?.unlockAvailableAt]);
+
+    if (!lock) return null;
+
+    const cooldownOver =
+        lock.unlockAvailableAt !== null && new Date(lock.unlockAvailableAt).getTime() <= now;
+
+    const run = (action: Promise<void>) => {
+        setError(null);
+        void action.then(() => setPhrase("")).catch((e) => setError(errorMessage(e)));
+    };
+
+    return (
+        <div className="session-lock">
+            <p className="session-lock__title">
+                🔒 Locked until {clockTime(lock.endsAt)}
+            </p>
+
+            {lock.unlockAvailableAt === null ? (
+                <button
+                    type="button"
+                    className="btn btn--secondary"
+                    onClick={() => run(onRequestUnlock())}
+                >
+                    Request unlock ({lock.cooldownMinutes} min wait)
+                </button>
+            ) : cooldownOver ? (
+                <button type="button" className="btn btn--secondary" onClick={() => run(onUnlock())}>
+                    Unlock now
+                </button>
+            ) : (
+                <p className="session-lock__hint">
+                    Unlock available at {clockTime(lock.unlockAvailableAt)}
+                </p>
+            )}
+
+            <label className="session-lock__phrase">
+                Or type “{lock.unlockPhrase}”
+                <input
+                    type="text"
+                    value={phrase}
+                    onChange={(e) => setPhrase(e.target.value)}
+                    onKeyDown={(e) => {
+                        if (e.key === "Enter") run(onUnlock(phrase));
+                    }}
+                />
+            </label>
+
+            {error && <p className="session-lock__error">{error}</p>}
+        </div>
+    );
+}
@@ -1,9 +1,18 @@
-import { useMemo, useState } from "react";
+import { useEffect, useMemo, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { useTimerStore } from "../store/timerStore";
-import type { Settings as SettingsModel, TodayStatistics } from "../types/timer";
+import type {
+  AppBlockAction,
+  BlockMode,
+  BlockingMethod,
+  BlocklistProfile,
+  Settings as SettingsModel,
+  TodayStatistics,
+} from "../types/timer";
+import { errorMessage } from "../types/error";
 
-const DEFAULT_SETTINGS: SettingsModel = {
+// Only the timer fields: resetting leaves blocking (method, hosts file, helper, addresses) alone.
+const DEFAULT_TIMER_SETTINGS: SettingsModel = {
   workMinutes: 25,
   shortBreakMinutes: 5,
   longBreakMinutes: 15,
@@ -22,9 +31,18 @@ export function Settings(props: { onClose: () => void }) {
   const setSettings = useTimerStore((s) => s.setSettings);
   const setTodayStats = useTimerStore((s) => s.setTodayStats);
 
-  const [draft, setDraft] = useState<SettingsModel>(current ?? DEFAULT_SETTINGS);
+  const [draft, setDraft] = useState<SettingsModel>(current ?? DEFAULT_TIMER_SETTINGS);
   const [saving, setSaving] = useState(false);
   const [error, setError] = useState<string | null>(null);
+  const [profiles, setProfiles] = useState<BlocklistProfile[]>([]);
+
+  useEffect(() => {
+    invoke("get_blocklist_profiles")
+      .then((list) => setProfiles(list as BlocklistProfile[]))
+      .catch(() => {
+        // Profiles are optional; the backend falls back to the first one.
+      });
+  }, []);
 
   const canSave = useMemo(() => {
     return !saving;
@@ -46,8 +64,7 @@ export function Settings(props: { onClose: () => void }) {
 
       props.onClose();
     } catch (e) {
-      const msg = typeof e === "string" ? e : "Failed to save settings";
-      setError(msg);
+      setError(errorMessage(e, "Failed to save settings"));
     } finally {
       setSaving(false);
     }
@@ -132,6 +149,136 @@ export function Settings(props: { onClose: () => void }) {
               onChange={(e) => setDraft((d) => ({ ...d, dailyGoal: toInt(e.target.value) }))}
             />
           </label>
+
+          {profiles.length > 0 && (
+            <label className="field">
+              <span className="field__label">Blocklist Profile</span>
+              <select
+                className="field__input"
+                value={draft.activeProfileId ?? profiles[0].id}
+                onChange={(e) =>
+                  setDraft((d) => ({ ...d, activeProfileId: toInt(e.target.value) }))
+                }
+              >
+                {profiles.map((p) => (
+                  <option key={p.id} value={p.id}>
+                    {p.name}
+                  </option>
+                ))}
+              </select>
+            </label>
+          )}
+
+          <label className="field">
+            <span className="field__label">Blocked Apps</span>
+            <select
+              className="field__input"
+              value={draft.appBlockAction ?? "terminate"}
+              onChange={(e) =>
+                setDraft((d) => ({ ...d, appBlockAction: e.target.value as AppBlockAction }))
+              }
+            >
+              <option value="terminate">Close them</option>
+              <option value="suspend">Freeze until the break</option>
+            </select>
+          </label>
+
+          <label className="field">
+            <span className="field__label">Blocking Method</span>
+            <select
+              className="field__input"
+              value={draft.blockingMethod ?? "hosts"}
+              onChange={(e) => {
+                const blockingMethod = e.target.value as BlockingMethod;
+                // Allowlist mode isn't available with the hosts file.
+                setDraft((d) => ({
+                  ...d,
+                  blockingMethod,
+                  blockMode: blockingMethod === "hosts" ? "blocklist" : d.blockMode,
+                }));
+              }}
+            >
+              <option value="hosts">Hosts file</option>
+              <option value="dns">Local DNS sinkhole</option>
+              <option value="proxy">Local proxy</option>
+            </select>
+          </label>
+
+          {(draft.blockingMethod ?? "hosts") === "hosts" && (
+            <>
+              <label className="field">
+                <span className="field__label">Hosts File Path</span>
+                <input
+                  className="field__input"
+                  type="text"
+                  placeholder="System default"
+                  value={draft.hostsPath ?? ""}
+                  onChange={(e) => setDraft((d) => ({ ...d, hostsPath: e.target.value || null }))}
+                />
+              </label>
+
+              <label className="field field--checkbox">
+                <input
+                  type="checkbox"
+                  checked={draft.useHostsHelper ?? false}
+                  onChange={(e) => setDraft((d) => ({ ...d, useHostsHelper: e.target.checked }))}
+                />
+                <span className="field__label">
+                  Use hosts helper (run focus-hosts-helper elevated instead of the whole app)
+                </span>
+              </label>
+            </>
+          )}
+
+          {draft.blockingMethod !== undefined && draft.blockingMethod !== "hosts" && (
+            <label className="field">
+              <span className="field__label">Site List Mode</span>
+              <select
+                className="field__input"
+                value={draft.blockMode ?? "blocklist"}
+                onChange={(e) => setDraft((d) => ({ ...d, blockMode: e.target.value as BlockMode }))}
+              >
+                <option value="blocklist">Block these sites</option>
+                <option value="allowlist">Only allow these sites</option>
+              </select>
+            </label>
+          )}
+
+          {draft.blockingMethod === "dns" && (
+            <>
+              <label className="field">
+                <span className="field__label">DNS Listen Address</span>
+                <input
+                  className="field__input"
+                  type="text"
+                  value={draft.dnsListenAddr ?? "127.0.0.1:53"}
+                  onChange={(e) => setDraft((d) => ({ ...d, dnsListenAddr: e.target.value }))}
+                />
+              </label>
+
+              <label className="field">
+                <span className="field__label">Upstream DNS Server</span>
+                <input
+                  className="field__input"
+                  type="text"
+                  value={draft.dnsUpstream ?? "1.1.1.1:53"}
+                  onChange={(e) => setDraft((d) => ({ ...d, dnsUpstream: e.target.value }))}
+                />
+              </label>
+            </>
+          )}
+
+          {draft.blockingMethod === "proxy" && (
+            <label className="field">
+              <span className="field__label">Proxy Listen Address</span>
+              <input
+                className="field__input"
+                type="text"
+                value={draft.proxyListenAddr ?? "127.0.0.1:8899"}
+                onChange={(e) => setDraft((d) => ({ ...d, proxyListenAddr: e.target.value }))}
+              />
+            </label>
+          )}
         </div>
 
         {error && <div className="form__error">{error}</div>}
@@ -140,9 +287,9 @@ export function Settings(props: { onClose: () => void }) {
           <button
             className="btn btn--ghost"
             type="button"
-            onClick={() => setDraft(DEFAULT_SETTINGS)}
+            onClick={() => setDraft((d) => ({ ...d, ...DEFAULT_TIMER_SETTINGS }))}
           >
-            Reset to defaults
+            Reset timer to defaults
           </button>
 
           <div className="modal__actionsRight">
@@ -40,7 +40,9 @@ export function Statistics() {
         </div>
         <div className="stats__card">
           <div className="stats__key">Breaks Taken</div>
-          <div className="stats__val">{stats.breaksTaken}</div>
+          <div className="stats__val">
+            {stats.breaksTaken} ({stats.shortBreaksTaken} short, {stats.longBreaksTaken} long)
+          </div>
         </div>
         <div className="stats__card">
           <div className="stats__key">Streak</div>
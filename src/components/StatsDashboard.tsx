@@ -27,7 +27,11 @@ export function StatsDashboard() {
     const totalFocusMinutes = stats?.totalFocusMinutes ?? 0;
     const sessionsCompleted = stats?.workSessionsCompleted ?? completedWorkSessions;
     const breaksTaken = stats?.breaksTaken ?? 0;
+    const shortBreaksTaken = stats?.shortBreaksTaken ?? 0;
+    const longBreaksTaken = stats?.longBreaksTaken ?? 0;
     const currentStreak = stats?.currentStreak ?? 0;
+    const interruptions = stats?.interruptions ?? 0;
+    const completionRate = stats?.completionRate ?? null;
     const goal = stats?.dailyGoal ?? dailyGoal;
 
     return (
@@ -48,11 +52,21 @@ export function StatsDashboard() {
                 <StatCard
                     label="Breaks"
                     value={breaksTaken}
+                    unit={`${shortBreaksTaken} short · ${longBreaksTaken} long`}
                 />
                 <StatCard
                     label="Streak"
                     value={currentStreak}
                 />
+                <StatCard
+                    label="Completion"
+                    value={completionRate === null ? "–" : Math.round(completionRate * 100)}
+                    unit={completionRate === null ? undefined : "%"}
+                />
+                <StatCard
+                    label="Interruptions"
+                    value={interruptions}
+                />
             </div>
         </div>
     );
@@ -1,13 +1,25 @@
 import { useTimerStore } from "../store/timerStore";
+import type { Phase } from "../types/timer";
 
 interface TimerControlsProps {
     onStart: () => Promise<void>;
+    onStartLocked: () => Promise<void>;
     onPause: () => Promise<void>;
     onReset: () => Promise<void>;
+    onSkip: () => Promise<void>;
+    onSetPhase: (phase: Phase) => Promise<void>;
 }
 
-export function TimerControls({ onStart, onPause, onReset }: TimerControlsProps) {
+export function TimerControls({
+    onStart,
+    onStartLocked,
+    onPause,
+    onReset,
+    onSkip,
+    onSetPhase,
+}: TimerControlsProps) {
     const isRunning = useTimerStore((s) => s.isRunning);
+    const phase = useTimerStore((s) => s.phase);
 
     const handleStartPause = () => {
         if (isRunning) {
@@ -50,6 +62,39 @@ export function TimerControls({ onStart, onPause, onReset }: TimerControlsProps)
             >
                 Reset
             </button>
+
+            <button
+                type="button"
+                className="timer-controls__btn timer-controls__btn--secondary"
+                onClick={() => void onSkip()}
+            >
+                Skip
+            </button>
+
+            <select
+                className="timer-controls__jump"
+                aria-label="Jump to phase"
+                value=""
+                onChange={(e) => void onSetPhase(e.target.value as Phase)}
+            >
+                <option value="" disabled>
+                    Jump to…
+                </option>
+                {phase !== "work" && <option value="work">Work</option>}
+                {phase !== "short_break" && <option value="short_break">Short Break</option>}
+                {phase !== "long_break" && <option value="long_break">Long Break</option>}
+            </select>
+
+            {/* Locked start: no pausing or resetting until the work phase ends */}
+            {!isRunning && phase === "work" && (
+                <button
+                    type="button"
+                    className="timer-controls__btn timer-controls__btn--secondary"
+                    onClick={() => void onStartLocked()}
+                >
+                    Start locked
+                </button>
+            )}
         </div>
     );
 }
@@ -8,8 +8,11 @@ import {
 } from "@tauri-apps/plugin-notification";
 import { useTimerStore } from "../store/timerStore";
 import type {
+  BlockerStatus,
+  HostsRecovery,
   Phase,
   PhaseCompleteEvent,
+  SessionLockStatus,
   Settings,
   TimerState,
   TodayStatistics,
@@ -31,6 +34,8 @@ function notificationBody(from: Phase, to: Phase): string {
   if (from === "work" && to === "short_break") return "Time to get back to work! 💪";
   if (from === "short_break" && to === "work") return "Take a quick 5-minute rest! ☕";
   if (from === "long_break" && to === "work") return "Enjoy your 15-minute break! 🌟";
+  // The break passed while the computer was asleep; the timer waits for the user.
+  if (from === "work" && to === "work") return "Work session done. Start the next one when you're ready.";
   return `${phaseLabel(from)} → ${phaseLabel(to)}`;
 }
 
@@ -38,7 +43,9 @@ export function useTimer() {
   const setFromBackend = useTimerStore((s) => s.setFromBackend);
   const setSettings = useTimerStore((s) => s.setSettings);
   const setTodayStats = useTimerStore((s) => s.setTodayStats);
-  const setBlockingError = useTimerStore((s) => s.setBlockingError);
+  const setBlockerStatus = useTimerStore((s) => s.setBlockerStatus);
+  const setHostsRecovery = useTimerStore((s) => s.setHostsRecovery);
+  const setSessionLock = useTimerStore((s) => s.setSessionLock);
 
   useEffect(() => {
     let mounted = true;
@@ -71,12 +78,26 @@ export function useTimer() {
       } catch {
         // Ignore if stats can't be loaded.
       }
+
+      try {
+        const status = (await invoke("get_blocker_status")) as BlockerStatus;
+        if (mounted) setBlockerStatus(status);
+      } catch {
+        // Later `blocker-status` events fill this in.
+      }
+
+      try {
+        const lock = (await invoke("get_session_lock")) as SessionLockStatus | null;
+        if (mounted) setSessionLock(lock);
+      } catch {
+        // Later `session-lock` events fill this in.
+      }
     })();
 
     return () => {
       mounted = false;
     };
-  }, [setFromBackend, setSettings, setTodayStats]);
+  }, [setFromBackend, setSettings, setTodayStats, setBlockerStatus, setSessionLock]);
 
   useEffect(() => {
     const unlistenState = listen<TimerState>("timer-state", (event) => {
@@ -84,7 +105,9 @@ export function useTimer() {
     });
 
     const unlistenPhase = listen<PhaseCompleteEvent>("phase-complete", (event) => {
-      const { from, to } = event.payload;
+      const { from, to, skipped } = event.payload;
+      // The user skipped it themselves; no need to tell them.
+      if (skipped) return;
       try {
         sendNotification({
           title: "Phase Complete",
@@ -102,10 +125,34 @@ export function useTimer() {
       },
     );
 
-    const unlistenBlockingError = listen<{ message: string }>(
-      "blocking-error",
+    const unlistenBlockerStatus = listen<BlockerStatus>(
+      "blocker-status",
       (event) => {
-        setBlockingError(event.payload.message);
+        setBlockerStatus(event.payload);
+        // A damaged hosts block can also turn up after startup.
+        if (event.payload.lastError?.code === "hosts_recovery_needed") {
+          invoke<HostsRecovery | null>("get_hosts_recovery")
+            .then((recovery) => {
+              if (recovery) setHostsRecovery(recovery);
+            })
+            .catch(() => {
+              // The error is already shown through `blockingError`.
+            });
+        }
+      },
+    );
+
+    const unlistenHostsRecovery = listen<HostsRecovery>(
+      "hosts-recovery-needed",
+      (event) => {
+        setHostsRecovery(event.payload);
+      },
+    );
+
+    const unlistenSessionLock = listen<SessionLockStatus | null>(
+      "session-lock",
+      (event) => {
+        setSessionLock(event.payload);
       },
     );
 
@@ -113,14 +160,16 @@ export function useTimer() {
       void unlistenState.then((fn) => fn());
       void unlistenPhase.then((fn) => fn());
       void unlistenTodayStats.then((fn) => fn());
-      void unlistenBlockingError.then((fn) => fn());
+      void unlistenBlockerStatus.then((fn) => fn());
+      void unlistenHostsRecovery.then((fn) => fn());
+      void unlistenSessionLock.then((fn) => fn());
     };
-  }, [setFromBackend, setTodayStats, setBlockingError]);
+  }, [setFromBackend, setTodayStats, setBlockerStatus, setHostsRecovery, setSessionLock]);
 
   return useMemo(
     () => ({
-      start: async () => {
-        await invoke("start_timer");
+      start: async (locked = false) => {
+        await invoke("start_timer", { locked });
       },
       pause: async () => {
         await invoke("pause_timer");
@@ -128,6 +177,21 @@ export function useTimer() {
       reset: async () => {
         await invoke("reset_timer");
       },
+      skip: async () => {
+        await invoke("skip_phase");
+      },
+      setPhase: async (phase: Phase) => {
+        await invoke("set_phase", { phase });
+      },
+      restoreHostsBackup: async () => {
+        await invoke("restore_hosts_backup");
+      },
+      requestUnlock: async () => {
+        await invoke("request_session_unlock");
+      },
+      unlock: async (phrase?: string) => {
+        await invoke("unlock_session", { phrase: phrase ?? null });
+      },
     }),
     [],
   );
@@ -1,14 +1,28 @@
 import { create } from "zustand";
-import type { Settings, TodayStatistics, TimerState } from "../types/timer";
+import type {
+  BlockerStatus,
+  HostsRecovery,
+  SessionLockStatus,
+  Settings,
+  TodayStatistics,
+  TimerState,
+} from "../types/timer";
+import { errorMessage } from "../types/error";
 
 type TimerStore = TimerState & {
   settings: Settings | null;
   todayStats: TodayStatistics | null;
   blockingError: string | null;
+  blockerStatus: BlockerStatus | null;
+  hostsRecovery: HostsRecovery | null;
+  sessionLock: SessionLockStatus | null;
   setFromBackend: (state: TimerState) => void;
   setSettings: (settings: Settings) => void;
   setTodayStats: (stats: TodayStatistics) => void;
   setBlockingError: (message: string | null) => void;
+  setBlockerStatus: (status: BlockerStatus) => void;
+  setHostsRecovery: (recovery: HostsRecovery | null) => void;
+  setSessionLock: (lock: SessionLockStatus | null) => void;
 };
 
 const defaultState: TimerState = {
@@ -26,8 +40,22 @@ export const useTimerStore = create<TimerStore>((set) => ({
   settings: null,
   todayStats: null,
   blockingError: null,
+  blockerStatus: null,
+  hostsRecovery: null,
+  sessionLock: null,
   setFromBackend: (state) => set(state),
   setSettings: (settings) => set({ settings }),
   setTodayStats: (todayStats) => set({ todayStats }),
   setBlockingError: (blockingError) => set({ blockingError }),
+  setBlockerStatus: (blockerStatus) =>
+    set(
+      blockerStatus.lastError
+        ? {
+            blockerStatus,
+            blockingError: `Focus-mode blocking: ${errorMessage(blockerStatus.lastError)}`,
+          }
+        : { blockerStatus },
+    ),
+  setHostsRecovery: (hostsRecovery) => set({ hostsRecovery }),
+  setSessionLock: (sessionLock) => set({ sessionLock }),
 }));
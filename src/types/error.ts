@@ -0,0 +1,46 @@
+/** Error returned by every backend command (`AppError` in `src-tauri/src/error.rs`). */
+export type AppError =
+  | { code: "validation"; field: string; message: string }
+  | {
+      code:
+        | "permission_denied"
+        | "mutex_poisoned"
+        | "database_locked"
+        | "database"
+        | "not_found"
+        | "conflict"
+        | "hosts_recovery_needed"
+        | "session_locked"
+        | "unsupported"
+        | "io";
+      message: string;
+    };
+
+export function isAppError(e: unknown): e is AppError {
+  return typeof e === "object" && e !== null && "code" in e && "message" in e;
+}
+
+/** What the user can do about an error, when there is something specific. */
+export function remediation(error: AppError): string | null {
+  switch (error.code) {
+    case "permission_denied":
+      return "Run the app as Administrator/root, or turn on the hosts helper in settings.";
+    case "database_locked":
+      return "Another copy of the app may be running; close it and try again.";
+    case "hosts_recovery_needed":
+      return "Restore the hosts file backup first.";
+    case "session_locked":
+      return "Finish the work session, or use the emergency unlock.";
+    default:
+      return null;
+  }
+}
+
+/** Message for display, with the remediation appended when there is one. */
+export function errorMessage(e: unknown, fallback = "Something went wrong"): string {
+  if (isAppError(e)) {
+    const hint = remediation(e);
+    return hint ? `${e.message}. ${hint}` : e.message;
+  }
+  return typeof e === "string" ? e : fallback;
+}
@@ -1,3 +1,5 @@
+import type { AppError } from "./error";
+
 export type Phase = "work" | "short_break" | "long_break";
 
 export interface TimerState {
@@ -14,6 +16,8 @@ export interface PhaseCompleteEvent {
   from: Phase;
   to: Phase;
   completedSeconds?: number;
+  /** `from` was skipped by the user rather than running out. */
+  skipped?: boolean;
 }
 
 export interface Settings {
@@ -22,14 +26,89 @@ export interface Settings {
   longBreakMinutes: number;
   longBreakAfter: number;
   dailyGoal: number;
+  activeProfileId?: number | null;
+  blockSubdomains?: string[];
+  /** Hosts file to edit instead of the platform default; empty or null uses the default. */
+  hostsPath?: string | null;
+  /** Send hosts file edits to the elevated `focus-hosts-helper`. */
+  useHostsHelper?: boolean;
+  blockingMethod?: BlockingMethod;
+  blockMode?: BlockMode;
+  dnsListenAddr?: string;
+  dnsUpstream?: string;
+  proxyListenAddr?: string;
+  appBlockAction?: AppBlockAction;
+}
+
+export type AppBlockAction = "terminate" | "suspend";
+
+export interface BlockedApp {
+  id: number;
+  name: string;
+  enabled: boolean;
+}
+
+export type BlockingMethod = "hosts" | "dns" | "proxy";
+
+export type BlockMode = "blocklist" | "allowlist";
+
+export interface BlocklistProfile {
+  id: number;
+  name: string;
+  suppressNotifications: boolean;
+  siteCount: number;
 }
 
 export interface TodayStatistics {
   date: string;
   totalFocusMinutes: number;
   workSessionsCompleted: number;
+  /** shortBreaksTaken + longBreaksTaken. */
   breaksTaken: number;
+  shortBreaksTaken: number;
+  longBreaksTaken: number;
+  /** Work phases cut short by a reset, a skip or closing the app. */
+  interruptions: number;
+  /** 0–1; null until a work phase has ended today. */
+  completionRate: number | null;
   currentStreak: number;
   longestStreak: number;
   dailyGoal: number;
 }
+
+export type NotificationState = "untouched" | "suppressed" | "unavailable";
+
+export interface BlockerStatus {
+  enabled: boolean;
+  backend: BlockingMethod;
+  allowlist: boolean;
+  domainCount: number;
+  notifications: NotificationState;
+  lastError: AppError | null;
+}
+
+export interface HostsRecovery {
+  hostsPath: string;
+  reason: string;
+  backupPath: string | null;
+}
+
+/** A locked work session (`SessionLockStatus` in `src-tauri/src/session_lock.rs`). */
+export interface SessionLockStatus {
+  endsAt: string;
+  /** Set once an unlock was requested; the cooldown unlock works from then on. */
+  unlockAvailableAt: string | null;
+  unlockPhrase: string;
+  cooldownMinutes: number;
+}
+
+/** Recurring blocking window (`BlockSchedule` in `src-tauri/src/models.rs`). */
+export interface BlockSchedule {
+  id: number;
+  /** 0 = Monday … 6 = Sunday. */
+  days: number[];
+  /** Local "HH:MM"; an end before the start runs past midnight. */
+  start: string;
+  end: string;
+  enabled: boolean;
+}